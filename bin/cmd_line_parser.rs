@@ -1,7 +1,7 @@
 use crate::jsrp_lib::*;
 use clap::{Args, Parser, Subcommand};
 use js_randomness_predictor::*;
-use std::path::PathBuf;
+use std::{ops::Range, path::PathBuf};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -18,16 +18,22 @@ pub enum Environments {
   /// Firefox
   Firefox(SharedArgs),
   /// Chrome
-  Chrome(SharedArgs),
+  Chrome(ChromeArgs),
   /// Safari
   Safari(SharedArgs),
+  /// Work out which environment generated the sequence, then predict with it
+  Detect(SharedArgs),
+  /// Find how many calls from the sequence a value was, or will be, returned, eg. one seen later in a log
+  Locate(LocateArgs),
+  /// Find out if another sequence came from the same context as the sequence, eg. the same tab, and how many calls apart
+  Link(LinkArgs),
 }
 
 #[derive(Parser, Clone, Debug)]
 pub struct SharedArgs {
-  /// Sequence of observed outputs [floating point required]
-  #[arg(short, long, required = true, value_parser = parse_strict_float, num_args = 1..)]
-  pub sequence: Vec<f64>,
+  /// Sequence of observed outputs [floating point required, or integers with --int-range]. Add '±tolerance' (or '+-tolerance') to values only known roughly, and 'index:' in front of every value if some calls weren't observed. Separate fragments with an unknown number of calls between them with '/'
+  #[arg(short, long, required_unless_present_any = ["load_state", "seed"], value_parser = parse_sequence_token, num_args = 1..)]
+  pub sequence: Vec<SequenceToken>,
 
   /// Number of predictions to make
   #[arg(
@@ -40,12 +46,64 @@ pub struct SharedArgs {
   pub predictions: usize,
 
   /// Expected prediction values
-  #[arg(short = 'x', long, required = false, value_parser = parse_strict_float, num_args = 1.., group = "preds_or_expected")]
-  pub expected: Option<Vec<f64>>,
+  #[arg(short = 'x', long, required = false, value_parser = parse_observed_value, num_args = 1.., group = "preds_or_expected")]
+  pub expected: Option<Vec<ObservedValue>>,
+
+  /// Predict only the value this many calls after the sequence, where 0 is the next one. Jumps there instead of stepping, so it can be far ahead
+  #[arg(long, required = false, group = "preds_or_expected")]
+  pub at: Option<u64>,
+
+  /// Predict the values from call A up to (not including) call B after the sequence, eg. '1000000..1000010'
+  #[arg(long, required = false, value_parser = parse_range, group = "preds_or_expected")]
+  pub range: Option<Range<u64>>,
+
+  /// Number of values, returned before the sequence, to recover
+  #[arg(short, long, required = false, default_value_t = 0)]
+  pub backward: usize,
+
+  /// The sequence is integers from 'Math.floor(Math.random() * N)', this is N. Takes more values!
+  #[arg(long, required = false)]
+  pub int_range: Option<u64>,
+
+  /// The sequence was rounded to this many decimal places, eg. with 'toFixed'
+  #[arg(long, required = false, conflicts_with_all = ["int_range", "float32"])]
+  pub digits: Option<i32>,
+
+  /// The sequence went through a 'Float32Array' (or 'Math.fround')
+  #[arg(long, required = false, conflicts_with = "int_range")]
+  pub float32: bool,
+
+  /// Leave out values of the sequence that don't fit the state most of the others do, eg. ones from another tab
+  #[arg(long, required = false)]
+  pub robust: bool,
+
+  /// Most calls that may have been skipped between fragments of the sequence
+  #[arg(long, required = false)]
+  pub max_gap: Option<usize>,
+
+  /// Solver that recovers the generator's state. z3 copes with values that give away too little for the native one, eg. dice rolls in Firefox and Safari, but is slower
+  #[arg(long, required = false, value_enum, default_value_t = SolverBackend::Native)]
+  pub solver: SolverBackend,
 
   /// Path to export results to. Must be a '.json' file!
   #[arg(short, long, required = false, value_parser = parse_export_path)]
   pub export: Option<ExportPath>,
+
+  /// Save where the predictor is, after the predictions, to this '.json' file. Pick it up again with --load-state
+  #[arg(long, required = false, value_parser = parse_state_path)]
+  pub save_state: Option<PathBuf>,
+
+  /// Pick up from a '.json' file saved with --save-state, instead of solving a sequence
+  #[arg(long, required = false, value_parser = parse_state_path, conflicts_with_all = ["sequence", "robust", "max_gap"])]
+  pub load_state: Option<PathBuf>,
+
+  /// Node and Chrome only: the '--random-seed' the run was started with (or Deno's '--seed'). Predicts from its first call, instead of solving a sequence
+  #[arg(long, required = false, allow_negative_numbers = true, conflicts_with_all = ["sequence", "load_state", "robust", "max_gap", "backward"])]
+  pub seed: Option<i32>,
+
+  // Set by `jsrp locate`, which runs the environment to look for a value instead of predicting.
+  #[arg(skip)]
+  pub locate: Option<Locate>,
 }
 
 #[derive(Clone, Debug, Args)]
@@ -53,8 +111,119 @@ pub struct NodeArgs {
   #[clap(flatten)]
   pub shared_args: SharedArgs,
 
-  #[arg(short, long, required = true)]
-  pub major_version: NodeJsMajorVersion,
+  /// Node.js version, eg. 'v24', 'v22.3.1', or the JSON of 'process.versions'
+  #[arg(short, long, required_unless_present = "load_state", conflicts_with = "load_state", value_parser = parse_node_version)]
+  pub major_version: Option<VersionArg>,
+
+  /// Keep predicting across cache pool refills, instead of stopping at the pool size
+  #[arg(long, required = false)]
+  pub pool_aware: bool,
+}
+
+#[derive(Clone, Debug, Args)]
+pub struct ChromeArgs {
+  #[clap(flatten)]
+  pub shared_args: SharedArgs,
+
+  /// Chrome version, eg. 'v138' or 'v138.0.7204.97'
+  #[arg(short, long, required_unless_present = "load_state", conflicts_with = "load_state", value_parser = parse_chrome_version)]
+  pub major_version: Option<VersionArg>,
+
+  /// Keep predicting across cache pool refills, instead of stopping at the pool size
+  #[arg(long, required = false)]
+  pub pool_aware: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct LocateArgs {
+  /// The value to look for. Must be exact, as 'Math.random()' returned it
+  #[arg(value_parser = parse_random_value)]
+  pub value: f64,
+
+  /// Most calls after, and before, the sequence to look
+  #[arg(long, required = false, default_value_t = 1_000_000)]
+  pub max_distance: u64,
+
+  /// Environment to solve the sequence of, as it would be given to predict, eg. 'node -m v24 -s ...'
+  #[command(subcommand)]
+  pub environment: LocateIn,
+}
+
+// The environments of `Environments` a value can be located in, which is all of them but `locate`.
+#[derive(Debug, Subcommand)]
+#[clap(rename_all = "lower")]
+pub enum LocateIn {
+  /// Node.js
+  Node(NodeArgs),
+  /// Firefox
+  Firefox(SharedArgs),
+  /// Chrome
+  Chrome(ChromeArgs),
+  /// Safari
+  Safari(SharedArgs),
+  /// Work out which environment generated the sequence, then locate the value in it
+  Detect(SharedArgs),
+}
+
+#[derive(Debug, Args)]
+pub struct LinkArgs {
+  // Global, so they go after the environment's arguments, which a list of values can't go in front of.
+  /// The other sequence, exactly as 'Math.random()' returned it and in call order. Can be from before or after the sequence
+  #[arg(short, long, global = true, value_parser = parse_random_value, num_args = 1..)]
+  pub other: Vec<f64>,
+
+  /// Most calls after, and before, the sequence to look for the other one
+  #[arg(long, global = true, default_value_t = 1_000_000)]
+  pub max_distance: u64,
+
+  /// Environment of the sequence, as it would be given to predict, eg. 'node -m v24 -s ...'
+  #[command(subcommand)]
+  pub environment: LinkIn,
+}
+
+// The environments of `Environments` two sequences can be linked in.
+#[derive(Debug, Subcommand)]
+#[clap(rename_all = "lower")]
+pub enum LinkIn {
+  /// Node.js
+  Node(NodeArgs),
+  /// Firefox
+  Firefox(SharedArgs),
+  /// Chrome
+  Chrome(ChromeArgs),
+  /// Safari
+  Safari(SharedArgs),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Locate {
+  pub value: f64,
+  pub max_distance: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum SequenceToken {
+  Value(ObservedValue),
+  // '/', calls we didn't see came between the values either side of it.
+  Gap,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ObservedValue {
+  // Which call returned it, when the calls in between weren't observed.
+  pub index: Option<usize>,
+  pub value: f64,
+  // How far the actual value could be from `value`, in either direction.
+  pub tolerance: Option<f64>,
+  // Written without a decimal point, which only `--int-range` allows.
+  pub is_integer: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct VersionArg {
+  // What we show the user, since JSON is a bit much to echo back.
+  pub label: String,
+  pub v8: V8Version,
 }
 
 #[derive(Debug, Clone)]
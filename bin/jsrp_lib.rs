@@ -1,34 +1,278 @@
 use crate::cmd_line_parser::*;
-use js_randomness_predictor::*;
+use js_randomness_predictor::{
+  errors::{InitError, listed},
+  *,
+};
 use serde::Serialize;
 use serde_json::{to_string_pretty, to_value};
-use std::{error::Error, fs, path::Path};
+use std::{
+  error::Error,
+  fs,
+  ops::Range,
+  path::{Path, PathBuf},
+};
 
 #[derive(Serialize)]
 pub struct PredictionResult {
   pub environment: String,
   pub sequence: Vec<f64>,
   pub predictions: Vec<f64>,
+  // Calls after the sequence before the first prediction, with `--at` or `--range`.
+  pub first_index: u64,
   pub expected: Vec<f64>,
   pub is_accurate: bool,
+  pub history: Vec<f64>,
+  pub gaps: Vec<usize>,
+  // Values of the sequence `--robust` left out.
+  pub rejected: Vec<usize>,
+}
+
+#[derive(Serialize)]
+pub struct LinkResult {
+  pub environment: String,
+  pub sequence: Vec<f64>,
+  pub other: Vec<f64>,
+  pub is_linked: bool,
+  // Where `other` starts, in calls from the one after `sequence`, for every distance it fits at.
+  pub indices: Vec<i64>,
+  pub pool: Option<i64>,
+  pub slot: Option<u8>,
+}
+
+#[derive(Serialize)]
+pub struct LocateResult {
+  pub environment: String,
+  pub sequence: Vec<f64>,
+  pub value: f64,
+  // Calls from the end of the sequence, see `Location`.
+  pub index: i64,
+  pub pool: Option<i64>,
+  pub slot: Option<u8>,
+}
+
+// The sequence as the solver sees it, with `Unknown` for calls that weren't observed. Empty for a
+// state picked up with `--load-state`.
+#[derive(Default)]
+pub struct Sequence {
+  pub observations: Vec<Observation>,
+  // Calls skipped between fragments, if the sequence had any.
+  pub gaps: Vec<usize>,
+  // Where each observation was in `--sequence`: the index it was given, or otherwise how many
+  // values came before it. `None` for calls that weren't observed.
+  pub positions: Vec<Option<usize>>,
+}
+
+// Node and Chrome hand out the same V8 cache, so they share its limits.
+pub trait V8CachePredictor: Predictor {
+  fn variant(&self) -> V8Variant;
+  fn set_pool_aware(&mut self, is_pool_aware: bool);
+  fn pool_index(&mut self) -> Result<u8, Box<dyn Error>>;
+  fn set_allow_ambiguous(&mut self, allows_ambiguity: bool);
+  fn set_robust(&mut self, is_robust: bool);
+}
+
+impl V8CachePredictor for NodePredictor {
+  fn variant(&self) -> V8Variant {
+    return NodePredictor::variant(self);
+  }
+
+  fn set_pool_aware(&mut self, is_pool_aware: bool) {
+    NodePredictor::set_pool_aware(self, is_pool_aware);
+  }
+
+  fn pool_index(&mut self) -> Result<u8, Box<dyn Error>> {
+    return NodePredictor::pool_index(self);
+  }
+
+  fn set_allow_ambiguous(&mut self, allows_ambiguity: bool) {
+    NodePredictor::set_allow_ambiguous(self, allows_ambiguity);
+  }
+
+  fn set_robust(&mut self, is_robust: bool) {
+    NodePredictor::set_robust(self, is_robust);
+  }
+}
+
+impl V8CachePredictor for ChromePredictor {
+  fn variant(&self) -> V8Variant {
+    return ChromePredictor::variant(self);
+  }
+
+  fn set_pool_aware(&mut self, is_pool_aware: bool) {
+    ChromePredictor::set_pool_aware(self, is_pool_aware);
+  }
+
+  fn pool_index(&mut self) -> Result<u8, Box<dyn Error>> {
+    return ChromePredictor::pool_index(self);
+  }
+
+  fn set_allow_ambiguous(&mut self, allows_ambiguity: bool) {
+    ChromePredictor::set_allow_ambiguous(self, allows_ambiguity);
+  }
+
+  fn set_robust(&mut self, is_robust: bool) {
+    ChromePredictor::set_robust(self, is_robust);
+  }
 }
 
 pub fn handle_node(node_args: NodeArgs) -> Result<(), Box<dyn Error>> {
-  let SharedArgs {
-    mut predictions,
+  let Some(version) = node_args.major_version else {
+    let state = load_state(&node_args.shared_args)?;
+    return run_v8_predictor(
+      NodePredictor::from_state(state)?,
+      format!("Node.js {}", state_label(&state)),
+      Sequence::default(),
+      node_args.shared_args,
+      node_args.pool_aware,
+    );
+  };
+  if let Some(seed) = node_args.shared_args.seed {
+    return run_v8_predictor(
+      NodePredictor::from_seed(version.v8, seed).map_err(|e| {
+        return e.to_string();
+      })?,
+      format!("Node.js {} (seed {seed})", version.label),
+      Sequence::default(),
+      node_args.shared_args,
+      node_args.pool_aware,
+    );
+  }
+  let engine = Engine::V8(version.v8.variant());
+  let sequence = read_sequence(&node_args.shared_args, Some(engine))?;
+  let observations = sequence.observations.clone();
+  let predictor =
+    NodePredictor::from_observations(version.v8, observations, node_args.shared_args.solver);
+  return run_v8_predictor(
+    predictor,
+    format!("Node.js {}", version.label),
+    sequence,
+    node_args.shared_args,
+    node_args.pool_aware,
+  );
+}
+
+pub fn handle_chrome(chrome_args: ChromeArgs) -> Result<(), Box<dyn Error>> {
+  let Some(version) = chrome_args.major_version else {
+    let state = load_state(&chrome_args.shared_args)?;
+    return run_v8_predictor(
+      ChromePredictor::from_state(state)?,
+      format!("Chrome {}", state_label(&state)),
+      Sequence::default(),
+      chrome_args.shared_args,
+      chrome_args.pool_aware,
+    );
+  };
+  if let Some(seed) = chrome_args.shared_args.seed {
+    return run_v8_predictor(
+      ChromePredictor::from_seed(version.v8, seed).map_err(|e| {
+        return e.to_string();
+      })?,
+      format!("Chrome {} (seed {seed})", version.label),
+      Sequence::default(),
+      chrome_args.shared_args,
+      chrome_args.pool_aware,
+    );
+  }
+  let engine = Engine::V8(version.v8.variant());
+  let sequence = read_sequence(&chrome_args.shared_args, Some(engine))?;
+  let observations = sequence.observations.clone();
+  let predictor =
+    ChromePredictor::from_observations(version.v8, observations, chrome_args.shared_args.solver);
+  return run_v8_predictor(
+    predictor,
+    format!("Chrome {}", version.label),
     sequence,
-    export,
-    mut expected,
-  } = node_args.shared_args;
+    chrome_args.shared_args,
+    chrome_args.pool_aware,
+  );
+}
+
+pub fn handle_detect(shared_args: SharedArgs) -> Result<(), Box<dyn Error>> {
+  // A value that fits no environment would be left out by all of them, so nothing tells them apart.
+  if shared_args.robust {
+    return Err(Box::from(
+      "Pick an environment to leave out values that don't fit it",
+    ));
+  }
+  if shared_args.load_state.is_some() {
+    return Err(Box::from("Pick the environment the state was saved from"));
+  }
+  if shared_args.seed.is_some() {
+    return Err(Box::from("Pick the environment the seed was given to"));
+  }
+  // Gaps depend on the engine, which is what we're trying to find out.
+  let sequence = read_sequence(&shared_args, None)?;
+  let mut detections = detect_with_solver(&sequence.observations, shared_args.solver);
+
+  if detections.len() != 1 {
+    let err_msg = if detections.is_empty() {
+      "\x1b[31m[ERROR] No known environment could have generated this sequence!\x1b[0m".to_string()
+    } else {
+      let engines: Vec<String> = detections
+        .iter()
+        .map(|detection| {
+          return format!(
+            "  - {} : {}",
+            detection.engine,
+            detection.engine.environments()
+          );
+        })
+        .collect();
+      format!(
+        "\x1b[33m[WARNING] The sequence fits more than one environment, try a longer sequence!\n{}\x1b[0m",
+        engines.join("\n")
+      )
+    };
+    println!("{err_msg}");
+    return Ok(());
+  }
+
+  let Detection { engine, predictor } = detections.remove(0);
+  return run_predictor(
+    predictor,
+    format!("{engine} ({})", engine.environments()),
+    sequence,
+    shared_args,
+  );
+}
+
+pub fn run_v8_predictor<P: V8CachePredictor>(
+  mut predictor: P,
+  environment: String,
+  sequence: Sequence,
+  mut shared_args: SharedArgs,
+  pool_aware: bool,
+) -> Result<(), Box<dyn Error>> {
+  // Calls that weren't observed still take their place in the pool.
+  let seq_len = sequence.observations.len();
+
+  let SharedArgs {
+    ref mut predictions,
+    ref mut expected,
+    int_range,
+    robust,
+    ..
+  } = shared_args;
+
+  // MWC1616 (before V8 4.9) has no cache, so there are no limits to enforce.
+  let has_cache = predictor.variant() != V8Variant::Mwc1616;
+  // Jumping ahead goes through as many refills as it takes.
+  let pool_aware = pool_aware
+    || !has_cache
+    || shared_args.at.is_some()
+    || shared_args.range.is_some()
+    || shared_args.locate.is_some()
+    // A seeded run starts at the top of its first cache, so where every refill is, is known.
+    || shared_args.seed.is_some();
 
-  let seq_len = sequence.len();
   let max_preds_usize = NodePredictor::MAX_NUM_PREDICTIONS as usize;
 
-  if let Some(ref expected_predictions) = expected {
-    predictions = expected_predictions.len();
+  if let Some(expected_predictions) = expected {
+    *predictions = expected_predictions.len();
   }
 
-  if seq_len >= max_preds_usize {
+  // Integers say less each, so it's fine for them to take more than one pool.
+  if has_cache && int_range.is_none() && seq_len >= max_preds_usize {
     let err_msg = format!(
       "\x1b[31m[ERROR] Sequence length exceeds limit! Max sequence length is {}!\nSee here for more : https://github.com/matthewoestreich/js-randomness-predictor-rust/blob/master/README.md#random-number-pool-exhaustion\x1b[0m",
       max_preds_usize - 1
@@ -37,30 +281,41 @@ pub fn handle_node(node_args: NodeArgs) -> Result<(), Box<dyn Error>> {
     return Ok(());
   }
 
-  let has_limit_error = (seq_len + predictions) > max_preds_usize;
+  predictor.set_pool_aware(pool_aware);
+  predictor.set_robust(robust);
+  // `run_predictor` warns when the sequence is too short to pin the state down.
+  predictor.set_allow_ambiguous(true);
+
+  // The sequence may have started part way into its pool, or crossed into the next one, so
+  // only the solver knows how many values are left before the next refill.
+  let pool_index = match predictor.pool_index() {
+    Ok(pool_index) => pool_index,
+    Err(error) => {
+      if let Some(err_msg) = unsolved_message(&*error, &environment, &sequence) {
+        println!("{err_msg}");
+        return Ok(());
+      }
+      return Err(error);
+    }
+  };
+  let remaining = max_preds_usize - pool_index as usize;
+
+  // In pool-aware mode the predictor follows V8 into the next pool, so nothing needs truncating.
+  let has_limit_error = !pool_aware && *predictions > remaining;
   if has_limit_error {
-    predictions = max_preds_usize - seq_len;
-    if let Some(ref mut exp) = expected {
-      exp.truncate(predictions);
+    *predictions = remaining;
+    if let Some(exp) = expected {
+      exp.truncate(remaining);
     }
   }
 
-  let major_ver = node_args.major_version;
-  let predictor = NodePredictor::new(major_ver, sequence.clone());
-
-  let prediction_result = run_predictor(
-    predictor,
-    format!("Node.js {major_ver}"),
-    sequence,
-    predictions,
-    expected,
-    export,
-  );
+  let predictions = *predictions;
+  let prediction_result = run_predictor(predictor, environment, sequence, shared_args);
 
   // If warning, log warning to console only after results have been logged!
   if has_limit_error {
     let warn_msg = format!(
-      "\x1b[33m[WARNING] Results have been truncated to {predictions}. Max prediction limit exceeded!\nSequence length + number of predictions cannot exceend {}!\nSee here for more : https://github.com/matthewoestreich/js-randomness-predictor-rust/blob/master/README.md#random-number-pool-exhaustion\x1b[0m",
+      "\x1b[33m[WARNING] Results have been truncated to {predictions}. Max prediction limit exceeded!\nOnly {remaining} of the {} values in the current pool were left after the sequence!\nSee here for more : https://github.com/matthewoestreich/js-randomness-predictor-rust/blob/master/README.md#random-number-pool-exhaustion\x1b[0m",
       NodePredictor::MAX_NUM_PREDICTIONS
     );
     println!("{warn_msg}");
@@ -69,6 +324,260 @@ pub fn handle_node(node_args: NodeArgs) -> Result<(), Box<dyn Error>> {
   return prediction_result;
 }
 
+// A value of the sequence, or the '/' between two of its fragments.
+pub fn parse_sequence_token(s: &str) -> Result<SequenceToken, String> {
+  if s == "/" {
+    return Ok(SequenceToken::Gap);
+  }
+  return Ok(SequenceToken::Value(parse_observed_value(s)?));
+}
+
+// Integers are only valid with `--int-range`, which `to_observation` checks once every arg is parsed.
+// Values only known roughly can carry a tolerance, eg. '0.1234±5e-5' (or '0.1234+-5e-5'), and
+// values can say which call returned them, eg. '7:0.88'.
+pub fn parse_observed_value(s: &str) -> Result<ObservedValue, String> {
+  let (index, s) = match s.split_once(':') {
+    Some((index, value)) => {
+      let index = index
+        .parse::<usize>()
+        .map_err(|e| format!("Invalid index: {e}"))?;
+      (Some(index), value)
+    }
+    None => (None, s),
+  };
+  let (value, tolerance) = match s.split_once('±').or_else(|| {
+    return s.split_once("+-");
+  }) {
+    Some((value, tolerance)) => {
+      let tolerance = tolerance
+        .parse::<f64>()
+        .map_err(|e| format!("Invalid tolerance: {e}"))?;
+      (value, Some(tolerance))
+    }
+    None => (s, None),
+  };
+  let (value, is_integer) = match value.parse::<i64>() {
+    Ok(integer) => (integer as f64, true),
+    Err(_) => (parse_strict_float(value)?, false),
+  };
+  return Ok(ObservedValue {
+    index,
+    value,
+    is_integer,
+    tolerance,
+  });
+}
+
+// What a value in `--sequence` (or `--expected`) tells us, which depends on the other flags.
+pub fn to_observation(
+  shared_args: &SharedArgs,
+  observed: &ObservedValue,
+) -> Result<Observation, Box<dyn Error>> {
+  let ObservedValue {
+    value,
+    tolerance,
+    is_integer,
+    ..
+  } = *observed;
+
+  if let Some(range) = shared_args.int_range {
+    if value.fract() != 0.0 || tolerance.is_some() {
+      return Err(Box::from(format!(
+        "Expected integers with --int-range, got '{value}'"
+      )));
+    }
+    return Ok(Observation::integer(value as i64, range)?);
+  }
+
+  check_random_value(value, is_integer)?;
+
+  return Ok(match (tolerance, shared_args.digits) {
+    (Some(tolerance), _) => Observation::Interval {
+      low: value - tolerance,
+      high: value + tolerance,
+    },
+    (None, Some(digits)) => Observation::rounded(value, digits),
+    (None, None) if shared_args.float32 => Observation::Float32(value as f32),
+    (None, None) => Observation::Exact(value),
+  });
+}
+
+// The sequence, with `Unknown` for calls that weren't observed. Between fragments, that takes
+// knowing the `engine` to search for how many calls were skipped.
+pub fn read_sequence(
+  shared_args: &SharedArgs,
+  engine: Option<Engine>,
+) -> Result<Sequence, Box<dyn Error>> {
+  let mut fragments = shared_args
+    .sequence
+    .split(|token| {
+      return matches!(token, SequenceToken::Gap);
+    })
+    .map(|tokens| {
+      return fragment_observations(shared_args, tokens);
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+  if fragments.iter().any(Vec::is_empty) {
+    return Err(Box::from(
+      "A '/' in the sequence needs values on both sides of it",
+    ));
+  }
+  if fragments.len() == 1 {
+    let observations = fragments.remove(0);
+    return Ok(Sequence {
+      positions: positions(shared_args, &observations),
+      observations,
+      gaps: vec![],
+    });
+  }
+
+  let Some(max_gap) = shared_args.max_gap else {
+    return Err(Box::from(
+      "Fragments separated by '/' need --max-gap, the most calls that could be between them",
+    ));
+  };
+  let Some(engine) = engine else {
+    return Err(Box::from(
+      "Pick an environment to search for calls skipped between fragments",
+    ));
+  };
+  let gaps = find_gaps(engine, &fragments, max_gap, shared_args.solver)?;
+  let observations = Observation::joined(&fragments, &gaps);
+  return Ok(Sequence {
+    positions: positions(shared_args, &observations),
+    observations,
+    gaps,
+  });
+}
+
+fn positions(shared_args: &SharedArgs, observations: &[Observation]) -> Vec<Option<usize>> {
+  let first_index = shared_args
+    .sequence
+    .iter()
+    .filter_map(|token| {
+      return match token {
+        SequenceToken::Value(observed) => observed.index,
+        SequenceToken::Gap => None,
+      };
+    })
+    .min();
+  let mut num_values = 0;
+  return observations
+    .iter()
+    .enumerate()
+    .map(|(i, observation)| {
+      if *observation == Observation::Unknown {
+        return None;
+      }
+      num_values += 1;
+      return Some(first_index.map_or(num_values - 1, |first| {
+        return first + i;
+      }));
+    })
+    .collect();
+}
+
+// Why the sequence couldn't be solved, in terms of the values as they were given, if it's because of
+// them.
+fn unsolved_message(
+  error: &(dyn Error + 'static),
+  environment: &str,
+  sequence: &Sequence,
+) -> Option<String> {
+  let observations = &sequence.observations;
+  return match error.downcast_ref::<InitError>() {
+    Some(InitError::NotEnoughObservations) => {
+      let known: f64 = observations.iter().map(Observation::information).sum();
+      let more = more_values(STATE_BITS - known, observations);
+      Some(format!(
+        "\x1b[31m[ERROR] The sequence is too short to solve for {environment}!\nAdd at least {more} more value(s) to the sequence.\x1b[0m"
+      ))
+    }
+    Some(InitError::Ambiguous) => {
+      let more = more_values(1.0, observations);
+      Some(format!(
+        "\x1b[31m[ERROR] More than one state of {environment} fits the sequence!\nAdd at least {more} more value(s) to the sequence to rule the others out.\x1b[0m"
+      ))
+    }
+    _ => inconsistency_message(error, environment, sequence),
+  };
+}
+
+// Why no state fits the sequence, in terms of the values as they were given, if that's the error.
+fn inconsistency_message(
+  error: &(dyn Error + 'static),
+  environment: &str,
+  sequence: &Sequence,
+) -> Option<String> {
+  let Some(InitError::Inconsistent { indices, culprit }) = error.downcast_ref::<InitError>() else {
+    return None;
+  };
+  let position = |index: &usize| {
+    return sequence.positions[*index].unwrap_or(*index);
+  };
+  let positions: Vec<usize> = indices.iter().map(position).collect();
+  let fix = match culprit {
+    Some(culprit) => format!(
+      "dropping value {} gives a unique solution",
+      position(culprit)
+    ),
+    None => "no single value can be dropped to fix it".to_string(),
+  };
+  let verb = if positions.len() == 1 { "is" } else { "are" };
+  return Some(format!(
+    "\x1b[31m[ERROR] The sequence's {} {verb} inconsistent with {environment}, {fix}!\x1b[0m",
+    listed("value", "values", &positions)
+  ));
+}
+
+// The values of one fragment, with `Unknown` for calls in between indexed values.
+fn fragment_observations(
+  shared_args: &SharedArgs,
+  tokens: &[SequenceToken],
+) -> Result<Vec<Observation>, Box<dyn Error>> {
+  let values: Vec<&ObservedValue> = tokens
+    .iter()
+    .filter_map(|token| {
+      return match token {
+        SequenceToken::Value(observed) => Some(observed),
+        SequenceToken::Gap => None,
+      };
+    })
+    .collect();
+  let num_indexed = values
+    .iter()
+    .filter(|observed| {
+      return observed.index.is_some();
+    })
+    .count();
+
+  if num_indexed == 0 {
+    return values
+      .into_iter()
+      .map(|observed| {
+        return to_observation(shared_args, observed);
+      })
+      .collect();
+  }
+  if num_indexed != shared_args.sequence.len() {
+    return Err(Box::from(
+      "Give every value in the sequence an index, or none of them (and no '/')",
+    ));
+  }
+
+  let indexed = values
+    .into_iter()
+    .map(|observed| {
+      return Ok((
+        observed.index.unwrap_or_default(),
+        to_observation(shared_args, observed)?,
+      ));
+    })
+    .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+  return Ok(Observation::sparse(indexed));
+}
+
 pub fn parse_strict_float(s: &str) -> Result<f64, String> {
   if s.contains('.') {
     return s.parse::<f64>().map_err(|e| format!("Invalid float: {e}"));
@@ -76,6 +585,47 @@ pub fn parse_strict_float(s: &str) -> Result<f64, String> {
   return Err(format!("Expected a float with decimal point, got '{s}'"));
 }
 
+// A value exactly as `Math.random()` returned it.
+pub fn parse_random_value(s: &str) -> Result<f64, String> {
+  return check_random_value(parse_strict_float(s)?, false);
+}
+
+// `Math.random()` returns floats from 0 up to, but never, 1.
+fn check_random_value(value: f64, is_integer: bool) -> Result<f64, String> {
+  if is_integer {
+    return Err(format!(
+      "Expected a float with decimal point, got '{value}'. Use --int-range for integers!"
+    ));
+  }
+  if !(0.0..1.0).contains(&value) {
+    return Err(format!(
+      "Expected floats from 0 up to (not including) 1, got '{value}'"
+    ));
+  }
+  return Ok(value);
+}
+
+pub fn parse_node_version(s: &str) -> Result<VersionArg, String> {
+  return parse_version(Runtime::Node, s);
+}
+
+pub fn parse_chrome_version(s: &str) -> Result<VersionArg, String> {
+  return parse_version(Runtime::Chrome, s);
+}
+
+fn parse_version(runtime: Runtime, s: &str) -> Result<VersionArg, String> {
+  let v8 = runtime.v8_version(s).map_err(|e| {
+    return e.to_string();
+  })?;
+  let s = s.trim();
+  let label = if s.starts_with('{') {
+    format!("(V8 {v8})")
+  } else {
+    format!("v{}", s.trim_start_matches(['v', 'V']))
+  };
+  return Ok(VersionArg { label, v8 });
+}
+
 pub fn parse_export_path(s: &str) -> Result<ExportPath, String> {
   let p = Path::new(s);
   if let Some(extension) = p.extension() {
@@ -91,48 +641,195 @@ pub fn parse_export_path(s: &str) -> Result<ExportPath, String> {
   return Err("Invalid export path! Path must be to a .json file!".into());
 }
 
+// 'A..B', the calls after the sequence `--range` predicts.
+pub fn parse_range(s: &str) -> Result<Range<u64>, String> {
+  let err_msg = || {
+    return format!("Expected a range like '100..110', got '{s}'");
+  };
+  let (start, end) = s.split_once("..").ok_or_else(err_msg)?;
+  let start: u64 = start.trim().parse().map_err(|_| {
+    return err_msg();
+  })?;
+  let end: u64 = end.trim().parse().map_err(|_| {
+    return err_msg();
+  })?;
+  if end <= start {
+    return Err(format!("Expected the end of '{s}' to be after its start"));
+  }
+  return Ok(start..end);
+}
+
+pub fn parse_state_path(s: &str) -> Result<PathBuf, String> {
+  if Path::new(s).extension().is_some_and(|extension| {
+    return extension == "json";
+  }) {
+    return Ok(s.into());
+  }
+  return Err(format!("Expected a path to a .json file, got '{s}'"));
+}
+
+// The state `--load-state` points to.
+pub fn load_state(shared_args: &SharedArgs) -> Result<PredictorState, Box<dyn Error>> {
+  let path = shared_args
+    .load_state
+    .as_ref()
+    .ok_or("Expected --load-state")?;
+  return Ok(serde_json::from_str(&fs::read_to_string(path)?)?);
+}
+
+fn save_state(path: &Path, state: &PredictorState) -> Result<(), Box<dyn Error>> {
+  fs::write(path, to_string_pretty(state)?)?;
+  return Ok(());
+}
+
+// Which V8 a loaded state is from, in place of the version given with '-m'.
+fn state_label(state: &PredictorState) -> String {
+  return match state.v8_version {
+    Some(v8) => format!("(V8 {v8})"),
+    None => format!("({})", state.engine),
+  };
+}
+
 pub fn run_predictor<P: Predictor>(
   mut predictor: P,
   environment: String,
-  sequence: Vec<f64>,
-  num_of_predictions: usize,
-  expected: Option<Vec<f64>>,
-  export_path: Option<ExportPath>,
+  sequence: Sequence,
+  shared_args: SharedArgs,
 ) -> Result<(), Box<dyn Error>> {
+  // Expected values are compared the same way the sequence was observed, eg. to `--digits`.
+  let expected = match &shared_args.expected {
+    Some(expected) => expected
+      .iter()
+      .map(|observed| {
+        if observed.index.is_some() {
+          return Err(Box::from(
+            "Expected values follow the sequence, they can't have an index",
+          ));
+        }
+        return to_observation(&shared_args, observed);
+      })
+      .collect::<Result<Vec<_>, _>>()?,
+    None => vec![],
+  };
+
+  // Predictions come from the first state that fits, so find out if it's the only one.
+  let candidates = match count_candidates(&mut predictor) {
+    Ok(candidates) => candidates,
+    Err(error) => {
+      if let Some(err_msg) = unsolved_message(&*error, &environment, &sequence) {
+        println!("{err_msg}");
+        return Ok(());
+      }
+      return Err(error);
+    }
+  };
+  let rejected: Vec<usize> = predictor
+    .rejected_indices()?
+    .into_iter()
+    .map(|index| {
+      return sequence.positions[index].unwrap_or(index);
+    })
+    .collect();
+
+  let SharedArgs {
+    sequence: tokens,
+    expected: expected_values,
+    predictions: num_of_predictions,
+    at,
+    range,
+    backward,
+    int_range,
+    export: export_path,
+    save_state: save_state_path,
+    locate,
+    ..
+  } = shared_args;
+
+  // With `--int-range`, report what the site would have shown, not the double behind it.
+  let scale = |value: f64| {
+    return match int_range {
+      Some(range) => (value * range as f64).floor(),
+      None => value,
+    };
+  };
+  let values = |observed: Vec<ObservedValue>| {
+    return observed
+      .into_iter()
+      .map(|observed| {
+        return observed.value;
+      })
+      .collect();
+  };
+  let observed = tokens
+    .into_iter()
+    .filter_map(|token| {
+      return match token {
+        SequenceToken::Value(observed) => Some(observed),
+        SequenceToken::Gap => None,
+      };
+    })
+    .collect();
+
   let mut pred_res = PredictionResult {
     environment,
-    sequence,
+    sequence: values(observed),
     predictions: vec![],
+    first_index: 0,
     is_accurate: false,
-    expected: vec![],
+    expected: values(expected_values.unwrap_or_default()),
+    history: vec![],
+    gaps: sequence.gaps,
+    rejected,
   };
 
-  let mut total_num_predictions = num_of_predictions;
+  // `jsrp locate` looks for a value, instead of predicting.
+  if let Some(locate) = locate {
+    locate_value(&mut predictor, &pred_res, locate, export_path)?;
+    if candidates > 1 {
+      println!("{}", ambiguity_warning(candidates, &sequence.observations));
+    }
+    return Ok(());
+  }
 
   // If user provided expected results, use the length of
   // them as 'predictions' (aka num of predictions, which is 10 by default).
   // Since users cannot use --predictions and --expected flags at the same
   // time, we only need to check for 'expected' flag.
-  if let Some(expected_preds) = expected {
-    pred_res.expected = expected_preds;
-    total_num_predictions = pred_res.expected.len();
-  }
+  let total_num_predictions = if expected.is_empty() {
+    num_of_predictions
+  } else {
+    expected.len()
+  };
+
+  // `--at` and `--range` jump past the calls before them, instead of predicting each one.
+  let (first_index, total_num_predictions) = match (at, range) {
+    (Some(index), _) => (index, 1),
+    (_, Some(range)) => (range.start, (range.end - range.start) as usize),
+    _ => (0, total_num_predictions),
+  };
+  predictor.skip(first_index)?;
+  pred_res.first_index = first_index;
 
-  // Make predictions.
-  for _ in 0..total_num_predictions {
+  // Make predictions, and validate them against expected results if the user provided them.
+  pred_res.is_accurate = !expected.is_empty();
+  for idx in 0..total_num_predictions {
     let pred = predictor.predict_next()?;
-    pred_res.predictions.push(pred);
+    if let Some(observation) = expected.get(idx) {
+      pred_res.is_accurate &= observation.matches(pred);
+    }
+    pred_res.predictions.push(scale(pred));
   }
 
-  // If user provided expected results, validate them.
-  if !pred_res.expected.is_empty() {
-    pred_res.is_accurate = true;
-    for (idx, pred) in pred_res.predictions.iter().enumerate() {
-      if *pred != pred_res.expected[idx] {
-        pred_res.is_accurate = false;
-        break;
-      }
-    }
+  // Recover values that were generated before the sequence, if user asked for them.
+  pred_res.history = predictor
+    .history(backward)?
+    .into_iter()
+    .map(scale)
+    .collect();
+
+  // After the predictions, so picking it up carries on from where they left off.
+  if let Some(path) = save_state_path {
+    save_state(&path, &predictor.state()?)?;
   }
 
   // Converts our struct to a JSON object.
@@ -147,6 +844,37 @@ pub fn run_predictor<P: Predictor>(
     json.remove("is_accurate");
   }
 
+  if pred_res.first_index == 0
+    && let Some(json) = json_pred_res.as_object_mut()
+  {
+    json.remove("first_index");
+  }
+
+  // A state picked up with `--load-state` has no sequence.
+  if pred_res.sequence.is_empty()
+    && let Some(json) = json_pred_res.as_object_mut()
+  {
+    json.remove("sequence");
+  }
+
+  if pred_res.history.is_empty()
+    && let Some(json) = json_pred_res.as_object_mut()
+  {
+    json.remove("history");
+  }
+
+  if pred_res.gaps.is_empty()
+    && let Some(json) = json_pred_res.as_object_mut()
+  {
+    json.remove("gaps");
+  }
+
+  if pred_res.rejected.is_empty()
+    && let Some(json) = json_pred_res.as_object_mut()
+  {
+    json.remove("rejected");
+  }
+
   // Log results to console so user can view them.
   let formatted = to_string_pretty(&json_pred_res)?;
   println!("{formatted}");
@@ -156,5 +884,303 @@ pub fn run_predictor<P: Predictor>(
     fs::write(export.path, formatted)?;
   }
 
+  // Like the pool warning, only after results have been logged.
+  if candidates > 1 {
+    println!("{}", ambiguity_warning(candidates, &sequence.observations));
+  }
+  if !pred_res.rejected.is_empty() {
+    let verb = if pred_res.rejected.len() == 1 {
+      "doesn't"
+    } else {
+      "don't"
+    };
+    println!(
+      "\x1b[33m[WARNING] Left out the sequence's {}, which {verb} fit {}!\x1b[0m",
+      listed("value", "values", &pred_res.rejected),
+      pred_res.environment
+    );
+  }
+
+  return Ok(());
+}
+
+// Where `locate` is from the sequence of `pred_res`, and where in V8's cache for Node and Chrome.
+fn locate_value<P: Predictor>(
+  predictor: &mut P,
+  pred_res: &PredictionResult,
+  locate: Locate,
+  export_path: Option<ExportPath>,
+) -> Result<(), Box<dyn Error>> {
+  let Some(location) = predictor.find_index(locate.value, locate.max_distance)? else {
+    println!(
+      "\x1b[33m[WARNING] {} isn't within {} calls of the sequence, in {}! Try a bigger --max-distance\x1b[0m",
+      locate.value, locate.max_distance, pred_res.environment
+    );
+    return Ok(());
+  };
+
+  let loc_res = LocateResult {
+    environment: pred_res.environment.clone(),
+    sequence: pred_res.sequence.clone(),
+    value: locate.value,
+    index: location.index,
+    pool: location.pool,
+    slot: location.slot,
+  };
+  let mut json_loc_res = to_value(&loc_res)?;
+  if let Some(json) = json_loc_res.as_object_mut() {
+    // Only Node and Chrome hand values out through a cache.
+    if loc_res.pool.is_none() {
+      json.remove("pool");
+      json.remove("slot");
+    }
+    if loc_res.sequence.is_empty() {
+      json.remove("sequence");
+    }
+  }
+
+  let formatted = to_string_pretty(&json_loc_res)?;
+  println!("{formatted}");
+  if let Some(export) = export_path {
+    fs::write(export.path, formatted)?;
+  }
+  return Ok(());
+}
+
+// The environment `jsrp locate` was given, told to look for the value instead of predicting.
+pub fn with_locate(locate_args: LocateArgs) -> Result<Environments, Box<dyn Error>> {
+  let mut environment = match locate_args.environment {
+    LocateIn::Node(node_args) => Environments::Node(node_args),
+    LocateIn::Firefox(args) => Environments::Firefox(args),
+    LocateIn::Chrome(chrome_args) => Environments::Chrome(chrome_args),
+    LocateIn::Safari(args) => Environments::Safari(args),
+    LocateIn::Detect(args) => Environments::Detect(args),
+  };
+  let shared_args = match &mut environment {
+    Environments::Node(node_args) => &mut node_args.shared_args,
+    Environments::Chrome(chrome_args) => &mut chrome_args.shared_args,
+    Environments::Firefox(args) | Environments::Safari(args) | Environments::Detect(args) => args,
+    Environments::Locate(_) | Environments::Link(_) => {
+      return Err(Box::from("Expected an environment to locate the value in"));
+    }
+  };
+  // Values are compared exactly, so one that only says part of a value can't be found.
+  if shared_args.int_range.is_some() || shared_args.digits.is_some() || shared_args.float32 {
+    return Err(Box::from(
+      "Locating needs the exact value, so it can't be used with --int-range, --digits or --float32",
+    ));
+  }
+  shared_args.locate = Some(Locate {
+    value: locate_args.value,
+    max_distance: locate_args.max_distance,
+  });
+  return Ok(environment);
+}
+
+pub fn handle_link(link_args: LinkArgs) -> Result<(), Box<dyn Error>> {
+  let LinkArgs {
+    other,
+    max_distance,
+    environment,
+  } = link_args;
+  if other.is_empty() {
+    return Err(Box::from("Expected the other sequence, with --other"));
+  }
+  let (environment, shared_args, linkage) = match environment {
+    LinkIn::Node(node_args) => {
+      let version = node_args.major_version.ok_or(LINK_LOAD_STATE_ERROR)?;
+      let sequence = exact_sequence(&node_args.shared_args)?;
+      let linkage = NodePredictor::link(version.v8, sequence, other.clone(), max_distance)?;
+      (
+        format!("Node.js {}", version.label),
+        node_args.shared_args,
+        linkage,
+      )
+    }
+    LinkIn::Firefox(args) => {
+      let linkage = FirefoxPredictor::link(exact_sequence(&args)?, other.clone(), max_distance)?;
+      ("Firefox".to_string(), args, linkage)
+    }
+    LinkIn::Chrome(chrome_args) => {
+      let version = chrome_args.major_version.ok_or(LINK_LOAD_STATE_ERROR)?;
+      let sequence = exact_sequence(&chrome_args.shared_args)?;
+      let linkage = ChromePredictor::link(version.v8, sequence, other.clone(), max_distance)?;
+      (
+        format!("Chrome {}", version.label),
+        chrome_args.shared_args,
+        linkage,
+      )
+    }
+    LinkIn::Safari(args) => {
+      let linkage = SafariPredictor::link(exact_sequence(&args)?, other.clone(), max_distance)?;
+      ("Safari".to_string(), args, linkage)
+    }
+  };
+
+  let locations = match linkage {
+    Linkage::Linked(locations) => locations,
+    Linkage::Unlinked => vec![],
+  };
+  let link_res = LinkResult {
+    environment,
+    sequence: exact_sequence(&shared_args)?,
+    other,
+    is_linked: !locations.is_empty(),
+    indices: locations
+      .iter()
+      .map(|location| {
+        return location.index;
+      })
+      .collect(),
+    pool: locations.first().and_then(|location| {
+      return location.pool;
+    }),
+    slot: locations.first().and_then(|location| {
+      return location.slot;
+    }),
+  };
+
+  let mut json_link_res = to_value(&link_res)?;
+  if let Some(json) = json_link_res.as_object_mut() {
+    if !link_res.is_linked {
+      json.remove("indices");
+    }
+    // Only Node and Chrome hand values out through a cache, and only one distance says where.
+    if link_res.pool.is_none() || link_res.indices.len() != 1 {
+      json.remove("pool");
+      json.remove("slot");
+    }
+  }
+
+  let formatted = to_string_pretty(&json_link_res)?;
+  println!("{formatted}");
+  if let Some(export) = shared_args.export {
+    fs::write(export.path, formatted)?;
+  }
+
+  if link_res.indices.len() > 1 {
+    println!(
+      "\x1b[33m[WARNING] The other sequence is from the same context, but fits {} distances from the sequence! Neither crossed a cache refill, which would say where in its cache it was\x1b[0m",
+      link_res.indices.len()
+    );
+  }
   return Ok(());
 }
+
+const LINK_LOAD_STATE_ERROR: &str =
+  "Linking solves the sequence, it can't pick up a saved state or start from a seed";
+
+// `--sequence` as plain values, for what needs them exactly as `Math.random()` returned them, in
+// call order.
+fn exact_sequence(shared_args: &SharedArgs) -> Result<Vec<f64>, Box<dyn Error>> {
+  if shared_args.load_state.is_some() || shared_args.seed.is_some() {
+    return Err(Box::from(LINK_LOAD_STATE_ERROR));
+  }
+  if shared_args.int_range.is_some() || shared_args.digits.is_some() || shared_args.float32 {
+    return Err(Box::from(
+      "Linking needs exact values, so it can't be used with --int-range, --digits or --float32",
+    ));
+  }
+  return shared_args
+    .sequence
+    .iter()
+    .map(|token| {
+      return match token {
+        SequenceToken::Value(ObservedValue {
+          index: None,
+          value,
+          tolerance: None,
+          is_integer,
+        }) => Ok(check_random_value(*value, *is_integer)?),
+        _ => Err(Box::from(
+          "Linking needs every value of the sequence, exactly and in call order",
+        )),
+      };
+    })
+    .collect();
+}
+
+// States counted before giving up, which is plenty to say the sequence is too short.
+const MAX_CANDIDATES: usize = 1024;
+// Bits of state to recover, both xorshift128+ and Firefox and Safari's generator keep 128.
+const STATE_BITS: f64 = 128.0;
+
+// How many states fit. Counting them all takes a search per state, so that's only done once a
+// second one says the warning is needed.
+fn count_candidates<P: Predictor>(predictor: &mut P) -> Result<usize, Box<dyn Error>> {
+  let candidates = predictor.candidate_states(2)?.len();
+  if candidates < 2 {
+    return Ok(candidates);
+  }
+  return Ok(predictor.candidate_states(MAX_CANDIDATES)?.len());
+}
+
+// How many states fit, and roughly how many more values like the ones given it takes to rule out
+// all but one of them.
+fn ambiguity_warning(candidates: usize, observations: &[Observation]) -> String {
+  let more = more_values((candidates as f64).log2(), observations);
+  let count = if candidates >= MAX_CANDIDATES {
+    format!("At least {candidates}")
+  } else {
+    candidates.to_string()
+  };
+  return format!(
+    "\x1b[33m[WARNING] {count} states fit the sequence, so predictions may come from the wrong one!\nAdd at least {more} more value(s) to the sequence to rule the others out.\x1b[0m"
+  );
+}
+
+// Roughly how many more values like the ones given it takes to learn `bits` more about the state.
+fn more_values(bits: f64, observations: &[Observation]) -> usize {
+  let known: Vec<f64> = observations
+    .iter()
+    .map(Observation::information)
+    .filter(|&bits| {
+      return bits > 0.0;
+    })
+    .collect();
+  // With nothing to go on, assume exact values.
+  let bits_per_value = if known.is_empty() {
+    53.0
+  } else {
+    known.iter().sum::<f64>() / known.len() as f64
+  };
+  return (bits / bits_per_value).ceil().max(1.0) as usize;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use clap::Parser;
+
+  // The sequence `jsrp node -m v24 ...` solves, with `args` after the version.
+  fn node_sequence(args: &[&str]) -> Result<Sequence, Box<dyn Error>> {
+    let cli = Cli::try_parse_from(["jsrp", "node", "-m", "v24"].iter().chain(args))?;
+    let Environments::Node(node_args) = cli.environments else {
+      return Err(Box::from("Expected node"));
+    };
+    return read_sequence(&node_args.shared_args, None);
+  }
+
+  #[test]
+  fn rejects_values_math_random_never_returns() -> Result<(), Box<dyn Error>> {
+    node_sequence(&["-s", "0.1", "0.0", "0.5", "0.2"])?;
+    assert!(node_sequence(&["-s", "0", "1", "0.5", "0.2"]).is_err());
+    assert!(node_sequence(&["-s", "0.1", "1.0", "0.5", "0.2"]).is_err());
+    node_sequence(&["--int-range", "6", "-s", "0", "1", "5", "2"])?;
+    return Ok(());
+  }
+
+  #[test]
+  fn says_how_many_more_values_it_takes() -> Result<(), Box<dyn Error>> {
+    let too_short = |args: &[&str]| {
+      let sequence = node_sequence(args)?;
+      return unsolved_message(&InitError::NotEnoughObservations, "Node.js v24", &sequence)
+        .ok_or(Box::<dyn Error>::from("Expected a message"));
+    };
+    assert!(too_short(&["-s", "0.5"])?.contains("at least 2 more value(s)"));
+    // Dice rolls give away under 3 bits each.
+    let rolls = ["--int-range", "6", "-s", "0", "1", "5", "2", "4"];
+    assert!(too_short(&rolls)?.contains("at least 45 more value(s)"));
+    return Ok(());
+  }
+}
@@ -8,10 +8,19 @@ use clap::Parser;
 use cmd_line_parser::*;
 use js_randomness_predictor::*;
 use jsrp_lib::*;
-use std::error::Error;
+use std::{error::Error, process::ExitCode};
 
-fn main() -> Result<(), Box<dyn Error>> {
-  match Cli::parse().environments {
+fn main() -> ExitCode {
+  // Shown with `Display`, the `Debug` form `main` would print says little more than the variant.
+  if let Err(error) = run(Cli::parse().environments) {
+    eprintln!("\x1b[31m[ERROR] {error}\x1b[0m");
+    return ExitCode::FAILURE;
+  }
+  return ExitCode::SUCCESS;
+}
+
+fn run(environments: Environments) -> Result<(), Box<dyn Error>> {
+  match environments {
     /*
       Node
     */
@@ -23,42 +32,70 @@ fn main() -> Result<(), Box<dyn Error>> {
       Firefox
     */
     Environments::Firefox(args) => {
-      return run_predictor(
-        FirefoxPredictor::new(args.sequence.clone()),
-        "Firefox".to_string(),
-        args.sequence,
-        args.predictions,
-        args.expected,
-        args.export,
-      );
+      if args.seed.is_some() {
+        return Err(Box::from(
+          "Only Node and Chrome can be predicted from a seed",
+        ));
+      }
+      if args.load_state.is_some() {
+        let predictor = FirefoxPredictor::from_state(load_state(&args)?)?;
+        return run_predictor(predictor, "Firefox".to_string(), Sequence::default(), args);
+      }
+      let sequence = read_sequence(&args, Some(Engine::FirefoxSafari))?;
+      let mut predictor =
+        FirefoxPredictor::from_observations(sequence.observations.clone(), args.solver);
+      predictor.set_allow_ambiguous(true);
+      predictor.set_robust(args.robust);
+      return run_predictor(predictor, "Firefox".to_string(), sequence, args);
     }
 
     /*
       Chrome
     */
-    Environments::Chrome(args) => {
-      return run_predictor(
-        ChromePredictor::new(args.sequence.clone()),
-        "Chrome".to_string(),
-        args.sequence,
-        args.predictions,
-        args.expected,
-        args.export,
-      );
+    Environments::Chrome(chrome_args) => {
+      return handle_chrome(chrome_args);
     }
 
     /*
       Safari
     */
     Environments::Safari(args) => {
-      return run_predictor(
-        SafariPredictor::new(args.sequence.clone()),
-        "Safari".to_string(),
-        args.sequence,
-        args.predictions,
-        args.expected,
-        args.export,
-      );
+      if args.seed.is_some() {
+        return Err(Box::from(
+          "Only Node and Chrome can be predicted from a seed",
+        ));
+      }
+      if args.load_state.is_some() {
+        let predictor = SafariPredictor::from_state(load_state(&args)?)?;
+        return run_predictor(predictor, "Safari".to_string(), Sequence::default(), args);
+      }
+      let sequence = read_sequence(&args, Some(Engine::FirefoxSafari))?;
+      let mut predictor =
+        SafariPredictor::from_observations(sequence.observations.clone(), args.solver);
+      predictor.set_allow_ambiguous(true);
+      predictor.set_robust(args.robust);
+      return run_predictor(predictor, "Safari".to_string(), sequence, args);
+    }
+
+    /*
+      Detect
+    */
+    Environments::Detect(args) => {
+      return handle_detect(args);
+    }
+
+    /*
+      Locate
+    */
+    Environments::Locate(locate_args) => {
+      return run(with_locate(locate_args)?);
+    }
+
+    /*
+      Link
+    */
+    Environments::Link(link_args) => {
+      return handle_link(link_args);
     }
 
     // Should never reach here, but still.
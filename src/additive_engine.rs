@@ -0,0 +1,669 @@
+use crate::{
+  Engine, Location, Observation, Predictor, PredictorState, SolverBackend,
+  additive_solver::AdditiveSolver,
+  diagnosis::diagnose,
+  errors::{InitError, JumpOverflowError, StateError},
+  jump::xor_shift_128_plus_jump,
+  link::{Linkage, link},
+  location::nearest,
+  observation::exact_values,
+  robust::outliers,
+  unordered::{Unordered, order},
+  untangle::{Untangled, untangle},
+  verification::Verification,
+};
+#[cfg(feature = "z3")]
+use crate::{diagnosis::unsat_core, observation::pins_state};
+use std::error::Error;
+#[cfg(feature = "z3")]
+use z3::{self, Config, Context, SatResult, Solver, ast::*};
+
+// xorshift128+, which Firefox and Safari both run, and convert to a double the same way.
+// `FirefoxPredictor` and `SafariPredictor` are wrappers around it.
+#[derive(Clone)]
+pub(crate) struct AdditiveEngine {
+  // The exact values observed, `observations` also has any that were only partly observed.
+  sequence: Vec<f64>,
+  observations: Vec<Observation>,
+  is_solved: bool,
+  solver: SolverBackend,
+  conc_state_0: u64,
+  conc_state_1: u64,
+  // State behind the earliest value returned so far, used by `predict_previous`.
+  prev_state_0: u64,
+  prev_state_1: u64,
+  // Predict from the first state found, even if others fit the observations too.
+  allows_ambiguity: bool,
+  // Work out which observations are to blame when no state fits, see `InitError::Inconsistent`.
+  diagnoses_unsat: bool,
+  // Leave out observations that don't fit the state most of the others do, see `set_robust`.
+  is_robust: bool,
+  rejected: Option<Vec<usize>>,
+  // What the solved state returns for each observed call, see `Verification`.
+  replayed: Vec<f64>,
+}
+
+impl Predictor for AdditiveEngine {
+  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?; // if solving fails, error is returned early
+    let v = self.xor_shift_128_plus_concrete();
+    return Ok(self.to_double(v));
+  }
+
+  fn predict_previous(&mut self) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let v = self.prev_state_0.wrapping_add(self.prev_state_1);
+    Self::xor_shift_128_plus_reverse(&mut self.prev_state_0, &mut self.prev_state_1);
+    return Ok(self.to_double(v));
+  }
+
+  fn skip(&mut self, n: u64) -> Result<(), Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    xor_shift_128_plus_jump(&mut self.conc_state_0, &mut self.conc_state_1, n);
+    return Ok(());
+  }
+
+  fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let steps = index.checked_add(1).ok_or(JumpOverflowError)?;
+    let (mut state_0, mut state_1) = (self.conc_state_0, self.conc_state_1);
+    xor_shift_128_plus_jump(&mut state_0, &mut state_1, steps);
+    return Ok(self.to_double(state_0.wrapping_add(state_1)));
+  }
+
+  fn find_index(
+    &mut self,
+    value: f64,
+    max_distance: u64,
+  ) -> Result<Option<Location>, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let mut ahead = self.clone();
+    // The last value returned came from the state we're at.
+    let (mut state_0, mut state_1) = (self.conc_state_0, self.conc_state_1);
+    let index = nearest(
+      value,
+      max_distance,
+      || {
+        let v = ahead.xor_shift_128_plus_concrete();
+        return ahead.to_double(v);
+      },
+      || {
+        let v = state_0.wrapping_add(state_1);
+        Self::xor_shift_128_plus_reverse(&mut state_0, &mut state_1);
+        return self.to_double(v);
+      },
+    );
+    return Ok(index.map(Location::new));
+  }
+
+  fn candidate_states(&mut self, limit: usize) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    if self.is_restored() {
+      return Ok(vec![(self.conc_state_0, self.conc_state_1)]);
+    }
+    self.reject_outliers()?;
+    return Ok(self.candidates(limit)?);
+  }
+
+  fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(self.rejected.clone().unwrap_or_default());
+  }
+
+  fn verify_observations(&mut self) -> Result<Verification, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(Verification::new(&self.observations, self.replayed.clone()));
+  }
+
+  fn state(&mut self) -> Result<PredictorState, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(PredictorState::new(
+      Engine::FirefoxSafari,
+      (self.conc_state_0, self.conc_state_1),
+      (self.prev_state_0, self.prev_state_1),
+    ));
+  }
+}
+
+impl AdditiveEngine {
+  #[cfg(feature = "z3")]
+  const SS_0_STR: &str = "sym_state_0";
+  #[cfg(feature = "z3")]
+  const SS_1_STR: &str = "sym_state_1";
+
+  // Enough exact values in a row that a window of them pins down one state, see `robust::outliers`.
+  const WINDOW_BITS: f64 = 300.0;
+
+  pub fn new(seq: Vec<f64>) -> Self {
+    return Self::with_solver(seq, SolverBackend::default());
+  }
+
+  pub fn with_solver(seq: Vec<f64>, solver: SolverBackend) -> Self {
+    let observations = seq.into_iter().map(Observation::from).collect();
+    return Self::from_observations(observations, solver);
+  }
+
+  pub fn from_observations(observations: Vec<Observation>, solver: SolverBackend) -> Self {
+    return AdditiveEngine {
+      sequence: exact_values(&observations),
+      observations,
+      is_solved: false,
+      solver,
+      conc_state_0: 0,
+      conc_state_1: 0,
+      prev_state_0: 0,
+      prev_state_1: 0,
+      allows_ambiguity: false,
+      diagnoses_unsat: true,
+      is_robust: false,
+      rejected: None,
+      replayed: vec![],
+    };
+  }
+
+  pub fn link(
+    seq_a: Vec<f64>,
+    seq_b: Vec<f64>,
+    max_distance: u64,
+  ) -> Result<Linkage, Box<dyn Error>> {
+    return link(
+      &seq_b,
+      max_distance,
+      |_| {
+        return Ok(Self::new(seq_a.clone()));
+      },
+      |_| {
+        return Ok(1);
+      },
+    );
+  }
+
+  // Static 'helper' method
+  // Splits `sequence` into the streams of up to `streams` contexts, each solved, and wrapped in
+  // whichever predictor asked.
+  pub(crate) fn untangle<P>(
+    sequence: &[f64],
+    streams: usize,
+    wrap: impl Fn(AdditiveEngine) -> P,
+  ) -> Result<Untangled<P>, Box<dyn Error>> {
+    let members = Engine::FirefoxSafari.untangle(sequence, streams)?;
+    return Untangled::build(sequence, members, |values| {
+      let mut engine = Self::with_solver(values, SolverBackend::default());
+      engine.solve_symbolic_state()?;
+      return Ok(wrap(engine));
+    });
+  }
+
+  // Static 'helper' method
+  // Indices of `sequence` in each of up to `streams` interleaved streams, see `untangle::untangle`.
+  // Four exact values pin the state, and since a window that doesn't fit takes the native solver
+  // about as long as one that does, the fewer values it takes the fewer windows there are to try.
+  pub(crate) fn untangle_members(
+    sequence: &[f64],
+    streams: usize,
+  ) -> Result<Vec<Vec<usize>>, InitError> {
+    return untangle(
+      sequence,
+      streams,
+      4,
+      |observations| {
+        let mut engine = Self::from_observations(observations.to_vec(), SolverBackend::Native);
+        engine.diagnoses_unsat = false;
+        return engine.candidates(2);
+      },
+      |&candidate, len| {
+        let calls = vec![Observation::Unknown; len];
+        return Self::from_observations(calls, SolverBackend::Native).outputs(candidate);
+      },
+    );
+  }
+
+  // Static 'helper' method
+  // Orders `values` within `max_spread` calls (at least as many as there are values), and solves
+  // for what comes after the last of them.
+  pub(crate) fn from_unordered<P>(
+    values: &[f64],
+    max_spread: Option<usize>,
+    wrap: impl Fn(AdditiveEngine) -> P,
+  ) -> Result<Unordered<P>, Box<dyn Error>> {
+    let spread = max_spread.unwrap_or(values.len()).max(values.len());
+    let positions = Self::order(values, spread)?;
+    return Unordered::build(values, positions, |observations| {
+      // The order was found with the native solver, so it's solved the same way.
+      let mut engine = Self::from_observations(observations, SolverBackend::Native);
+      engine.solve_symbolic_state()?;
+      return Ok(wrap(engine));
+    });
+  }
+
+  // Static 'helper' method
+  // Which call, of `spread` in a row, returned each of `values`, see `unordered::order`. Four
+  // exact values pin the state, and a fifth, when there is one, lets the native solver rule a
+  // placement out several times quicker than it takes to solve four.
+  fn order(values: &[f64], spread: usize) -> Result<Vec<usize>, InitError> {
+    return order(
+      values,
+      spread,
+      values.len().clamp(4, 5),
+      |observations| {
+        let mut engine = Self::from_observations(observations.to_vec(), SolverBackend::Native);
+        engine.diagnoses_unsat = false;
+        return engine.candidates(2);
+      },
+      |&candidate, len| {
+        let calls = vec![Observation::Unknown; len];
+        return Self::from_observations(calls, SolverBackend::Native).outputs(candidate);
+      },
+    );
+  }
+
+  pub fn from_state(state: PredictorState) -> Result<Self, StateError> {
+    state.check(|engine| {
+      return engine == Engine::FirefoxSafari;
+    })?;
+    let mut predictor = Self::new(vec![]);
+    (predictor.conc_state_0, predictor.conc_state_1) = (state.state_0, state.state_1);
+    (predictor.prev_state_0, predictor.prev_state_1) = (state.prev_state_0, state.prev_state_1);
+    predictor.is_solved = true;
+    return Ok(predictor);
+  }
+
+  #[allow(dead_code)]
+  pub fn sequence(&self) -> &[f64] {
+    return &self.sequence;
+  }
+
+  pub fn set_allow_ambiguous(&mut self, allows_ambiguity: bool) {
+    self.allows_ambiguity = allows_ambiguity;
+  }
+
+  pub fn set_robust(&mut self, is_robust: bool) {
+    self.is_robust = is_robust;
+  }
+
+  pub(crate) fn set_diagnose_unsat(&mut self, diagnoses_unsat: bool) {
+    self.diagnoses_unsat = diagnoses_unsat;
+  }
+
+  // Picked up from a `PredictorState`, so there's no sequence, only the state it was given.
+  fn is_restored(&self) -> bool {
+    return self.is_solved && self.observations.is_empty();
+  }
+
+  fn xor_shift_128_plus_concrete(&mut self) -> u64 {
+    let mut s1 = self.conc_state_0;
+    let s0 = self.conc_state_1;
+    self.conc_state_0 = s0;
+    s1 = s1 ^ s1 << 23;
+    self.conc_state_1 = s1 ^ s0 ^ (s1 >> 17) ^ (s0 >> 26);
+    return self.conc_state_1.wrapping_add(s0);
+  }
+
+  // Static 'helper' method
+  fn xor_shift_128_plus_reverse(state_0: &mut u64, state_1: &mut u64) {
+    let t1 = *state_0;
+    let mut t0 = *state_1 ^ (*state_0 >> 26);
+    t0 ^= *state_0;
+    t0 ^= (t0 >> 17) ^ (t0 >> 34) ^ (t0 >> 51);
+    t0 ^= (t0 << 23) ^ (t0 << 46);
+    *state_0 = t0;
+    *state_1 = t1;
+  }
+
+  fn to_double(&self, value: u64) -> f64 {
+    return ((value & 0x1FFFFFFFFFFFFF) as f64) / ((1u64 << 53) as f64);
+  }
+
+  pub(crate) fn solve_symbolic_state(&mut self) -> Result<(), InitError> {
+    if self.is_solved {
+      return Ok(());
+    }
+    self.reject_outliers()?;
+
+    // A second candidate is all it takes to know predictions could come from the wrong state.
+    let limit = if self.allows_ambiguity { 1 } else { 2 };
+    let candidates = self.candidates(limit)?;
+    if candidates.len() > 1 {
+      return Err(InitError::Ambiguous);
+    }
+    (self.conc_state_0, self.conc_state_1) = candidates[0];
+    self.replayed = self.outputs(candidates[0]);
+    Verification::new(&self.observations, self.replayed.clone()).check()?;
+
+    // Walk back to the state the first value of the sequence was generated from.
+    self.prev_state_0 = self.conc_state_0;
+    self.prev_state_1 = self.conc_state_1;
+    for _ in 0..self.observations.len() {
+      Self::xor_shift_128_plus_reverse(&mut self.prev_state_0, &mut self.prev_state_1);
+    }
+
+    self.is_solved = true;
+    return Ok(());
+  }
+
+  // Sets the observations that don't fit aside as unknown calls, once, if robust.
+  fn reject_outliers(&mut self) -> Result<(), InitError> {
+    if !self.is_robust || self.rejected.is_some() {
+      return Ok(());
+    }
+    let rejected = outliers(
+      &self.observations,
+      Self::WINDOW_BITS,
+      |observations, limit| {
+        // The native solver rules a window out far faster than z3, so use it wherever it can.
+        let solver = if observations.iter().all(|observation| {
+          return observation.is_exact() || *observation == Observation::Unknown;
+        }) {
+          SolverBackend::Native
+        } else {
+          self.solver
+        };
+        let mut predictor = Self::from_observations(observations.to_vec(), solver);
+        predictor.diagnoses_unsat = false;
+        return predictor.candidates(limit);
+      },
+      |&state| {
+        return self.outputs(state);
+      },
+    )?;
+    for &i in &rejected {
+      self.observations[i] = Observation::Unknown;
+    }
+    self.sequence = exact_values(&self.observations);
+    self.rejected = Some(rejected);
+    return Ok(());
+  }
+
+  // What a candidate state after the sequence would have returned for each call of it.
+  fn outputs(&self, (mut state_0, mut state_1): (u64, u64)) -> Vec<f64> {
+    let mut values = vec![0.0; self.observations.len()];
+    for value in values.iter_mut().rev() {
+      *value = self.to_double(state_0.wrapping_add(state_1));
+      Self::xor_shift_128_plus_reverse(&mut state_0, &mut state_1);
+    }
+    return values;
+  }
+
+  // States after the sequence that fit the observations, up to `limit` of them.
+  fn candidates(&self, limit: usize) -> Result<Vec<(u64, u64)>, InitError> {
+    let candidates = match self.solver {
+      SolverBackend::Native => self.native_candidates(limit),
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3 => self.z3_candidates(limit),
+    };
+    if self.diagnoses_unsat && matches!(candidates, Err(InitError::Unsat)) {
+      return Err(self.diagnose());
+    }
+    return candidates;
+  }
+
+  // Which observations are to blame for no state fitting.
+  fn diagnose(&self) -> InitError {
+    let core = match self.solver {
+      SolverBackend::Native => None,
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3 => self.z3_unsat_core(),
+    };
+    let is_possible = |observation: &Observation| {
+      return Self::mantissa_bounds(observation).is_some();
+    };
+    return diagnose(&self.observations, core, is_possible, |observations| {
+      let mut predictor = Self::from_observations(observations.to_vec(), self.solver);
+      predictor.diagnoses_unsat = false;
+      return Ok(predictor.candidates(2)?.len());
+    });
+  }
+
+  fn native_candidates(&self, limit: usize) -> Result<Vec<(u64, u64)>, InitError> {
+    return AdditiveSolver::new(&self.observations).solve(limit);
+  }
+
+  #[cfg(feature = "z3")]
+  fn z3_candidates(&self, limit: usize) -> Result<Vec<(u64, u64)>, InitError> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+
+    let bounds = self.observation_bounds()?;
+    let limit = if pins_state(&bounds, 53, 128) {
+      1
+    } else {
+      limit
+    };
+
+    let (sym_state_0, sym_state_1, constraints) = Self::z3_constraints(&context, &bounds);
+    for constraint in &constraints {
+      solver.assert(constraint);
+    }
+
+    let mut candidates = vec![];
+    while candidates.len() < limit && solver.check() == SatResult::Sat {
+      let model = solver.get_model().ok_or(InitError::MissingModel)?;
+
+      let state_0 = model
+        .eval(&sym_state_0, true)
+        .ok_or(InitError::EvalFailed(Self::SS_0_STR))?
+        .as_u64()
+        .ok_or(InitError::ConvertFailed(Self::SS_0_STR))?;
+
+      let state_1 = model
+        .eval(&sym_state_1, true)
+        .ok_or(InitError::EvalFailed(Self::SS_1_STR))?
+        .as_u64()
+        .ok_or(InitError::ConvertFailed(Self::SS_1_STR))?;
+
+      // Rule this state out, so the next check finds another one if there is one.
+      solver.assert(&Bool::or(
+        &context,
+        &[
+          &sym_state_0._eq(&BV::from_u64(&context, state_0, 64)).not(),
+          &sym_state_1._eq(&BV::from_u64(&context, state_1, 64)).not(),
+        ],
+      ));
+      candidates.push((state_0, state_1));
+    }
+
+    if candidates.is_empty() {
+      return Err(InitError::Unsat);
+    }
+    return Ok(candidates);
+  }
+
+  // Observations that z3 found can't all be right, if they can't.
+  #[cfg(feature = "z3")]
+  fn z3_unsat_core(&self) -> Option<Vec<usize>> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let bounds = self.observation_bounds().ok()?;
+    let (_, _, constraints) = Self::z3_constraints(&context, &bounds);
+    return unsat_core(&context, &Solver::new(&context), &constraints);
+  }
+
+  #[cfg(feature = "z3")]
+  fn observation_bounds(&self) -> Result<Vec<(u64, u64)>, InitError> {
+    return self
+      .observations
+      .iter()
+      .map(|observed| {
+        return Self::mantissa_bounds(observed).ok_or(InitError::Unsat);
+      })
+      .collect();
+  }
+
+  // Static 'helper' method
+  // Smallest and largest 53 bit mantissa that explains `observation`.
+  fn mantissa_bounds(observation: &Observation) -> Option<(u64, u64)> {
+    return observation.bounds(53, |mantissa| {
+      return mantissa as f64 / (1u64 << 53) as f64;
+    });
+  }
+
+  // Static 'helper' method
+  // The symbolic state after the sequence, and a constraint for each observation.
+  #[cfg(feature = "z3")]
+  fn z3_constraints<'a>(
+    context: &'a Context,
+    bounds: &[(u64, u64)],
+  ) -> (BV<'a>, BV<'a>, Vec<Bool<'a>>) {
+    let mut sym_state_0 = BV::new_const(context, Self::SS_0_STR, 64);
+    let mut sym_state_1 = BV::new_const(context, Self::SS_1_STR, 64);
+    let mut constraints = vec![];
+    for &observed in bounds {
+      Self::xor_shift_128_plus_symbolic(context, &mut sym_state_0, &mut sym_state_1);
+      constraints.push(Self::mantissa_constraint(
+        observed,
+        context,
+        &sym_state_0,
+        &sym_state_1,
+      ));
+    }
+    return (sym_state_0, sym_state_1, constraints);
+  }
+
+  // Static 'helper' method
+  #[cfg(feature = "z3")]
+  fn xor_shift_128_plus_symbolic<'a>(
+    context: &'a Context,
+    state_0: &mut BV<'a>,
+    state_1: &mut BV<'a>,
+  ) {
+    let state_0_shifted_left = state_0.bvshl(&BV::from_u64(context, 23, 64));
+    let mut s1 = &*state_0 ^ state_0_shifted_left;
+    let s1_shifted_right = s1.bvlshr(&BV::from_u64(context, 17, 64));
+
+    s1 ^= s1_shifted_right;
+    s1 ^= state_1.clone();
+    s1 ^= state_1.bvlshr(&BV::from_u64(context, 26, 64));
+    std::mem::swap(state_0, state_1);
+    *state_1 = s1;
+  }
+
+  // Static 'helper' method
+  #[cfg(feature = "z3")]
+  fn mantissa_constraint<'a>(
+    (low, high): (u64, u64),
+    context: &'a Context,
+    state_0: &BV<'a>,
+    state_1: &BV<'a>,
+  ) -> Bool<'a> {
+    let symbolic_mask = BV::from_u64(context, 0x1FFFFFFFFFFFFF, 64);
+    let masked = state_0.bvadd(state_1).bvand(&symbolic_mask);
+    if low == high {
+      return BV::from_u64(context, low, 64)._eq(&masked).simplify();
+    }
+    return Bool::and(
+      context,
+      &[
+        &masked.bvuge(&BV::from_u64(context, low, 64)),
+        &masked.bvule(&BV::from_u64(context, high, 64)),
+      ],
+    );
+  }
+}
+
+// Everything `FirefoxPredictor` and `SafariPredictor` have, for `$name`, which keeps its
+// `AdditiveEngine` in `engine`.
+macro_rules! additive_predictor {
+  ($name:ident) => {
+    impl $crate::Predictor for $name {
+      $crate::predictor::delegate_to_engine!(engine);
+
+      fn state(&mut self) -> Result<$crate::PredictorState, Box<dyn std::error::Error>> {
+        return $crate::Predictor::state(&mut self.engine);
+      }
+    }
+
+    impl $name {
+      pub fn new(seq: Vec<f64>) -> Self {
+        return Self::with_solver(seq, $crate::SolverBackend::default());
+      }
+
+      pub fn with_solver(seq: Vec<f64>, solver: $crate::SolverBackend) -> Self {
+        let observations = seq.into_iter().map($crate::Observation::from).collect();
+        return Self::from_observations(observations, solver);
+      }
+
+      // For when only part of each value leaked, eg. `Observation::integer(roll, 6)` for a dice
+      // roll. Partial observations say less each, so it takes more of them.
+      pub fn from_observations(
+        observations: Vec<$crate::Observation>,
+        solver: $crate::SolverBackend,
+      ) -> Self {
+        return $name {
+          engine: $crate::additive_engine::AdditiveEngine::from_observations(observations, solver),
+        };
+      }
+
+      // Whether `seq_b` came from the same generator as `seq_a`, eg. the same tab, and how many
+      // calls apart, looking up to `max_distance` calls either way. `seq_a` has to be enough to
+      // solve.
+      pub fn link(
+        seq_a: Vec<f64>,
+        seq_b: Vec<f64>,
+        max_distance: u64,
+      ) -> Result<$crate::Linkage, Box<dyn std::error::Error>> {
+        return $crate::additive_engine::AdditiveEngine::link(seq_a, seq_b, max_distance);
+      }
+
+      // Splits a sequence logged from several contexts (eg. iframes or workers, which each have a
+      // generator of their own) into up to `streams` of them, with a solved predictor for each.
+      // Each context needs 4 values in its stream.
+      pub fn untangle(
+        sequence: Vec<f64>,
+        streams: usize,
+      ) -> Result<$crate::Untangled<Self>, Box<dyn std::error::Error>> {
+        return $crate::additive_engine::AdditiveEngine::untangle(&sequence, streams, |engine| {
+          return $name { engine };
+        });
+      }
+
+      // For values collected with no call order, eg. from parallel requests. Finds which call
+      // returned each, all within `max_spread` calls in a row (by default as many as there are
+      // values, so none were missed), and a predictor for what comes after the last of them.
+      // Takes 4 values.
+      pub fn from_unordered(
+        values: Vec<f64>,
+        max_spread: Option<usize>,
+      ) -> Result<$crate::Unordered<Self>, Box<dyn std::error::Error>> {
+        return $crate::additive_engine::AdditiveEngine::from_unordered(
+          &values,
+          max_spread,
+          |engine| {
+            return $name { engine };
+          },
+        );
+      }
+
+      // Picks up where a Firefox or Safari predictor's `state` left off, without solving again.
+      pub fn from_state(state: $crate::PredictorState) -> Result<Self, $crate::errors::StateError> {
+        return Ok($name {
+          engine: $crate::additive_engine::AdditiveEngine::from_state(state)?,
+        });
+      }
+
+      // A copy to try what-ifs with, which predicts on its own from where this one is.
+      pub fn fork(&self) -> Self {
+        return self.clone();
+      }
+
+      #[allow(dead_code)]
+      pub fn sequence(&self) -> &[f64] {
+        return self.engine.sequence();
+      }
+
+      $crate::predictor::predictor_methods!();
+
+      // Without this, solving fails with `InitError::Ambiguous` when more than one state fits.
+      pub fn set_allow_ambiguous(&mut self, allows_ambiguity: bool) {
+        self.engine.set_allow_ambiguous(allows_ambiguity);
+      }
+
+      // Predict from the largest part of the sequence one state fits, leaving out values that
+      // came from somewhere else. `rejected_indices` says which ones were left out.
+      pub fn set_robust(&mut self, is_robust: bool) {
+        self.engine.set_robust(is_robust);
+      }
+    }
+  };
+}
+
+pub(crate) use additive_predictor;
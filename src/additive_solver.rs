@@ -0,0 +1,668 @@
+use crate::{
+  Observation,
+  errors::InitError,
+  gf2_solver::{
+    Elimination, EquationSet, LinearWord, MAX_FREE_VARIABLES, Reduction, free_assignments,
+    xor_shift_128_plus_linear,
+  },
+};
+use std::collections::HashMap;
+
+// Native solver for engines whose output is `(state_0 + state_1) & 0x1FFFFFFFFFFFFF` (Firefox and Safari).
+//
+// The addition is not linear over GF(2), so we guess carry bits instead. Every output
+// is `S[k] + S[k + 1]`, where `S` is the sequence of words the generator shifts through.
+// Knowing the low `j` bits of `S[1]` gives us the low `j` bits of every other word (and
+// every carry), so at most one bit has to be guessed per bit position. Everything else
+// becomes linear equations, and a guess is abandoned as soon as they are inconsistent.
+//
+// The equations are the same on every branch, only their right hand sides differ, so the
+// elimination is done once up front and each branch only has to check parities. A few exact
+// values leave some 25 bits to guess before the first check, so rather than guessing them all in
+// a row, the search meets in the middle when that's quicker, see `meet_in_the_middle`.
+//
+// Values we only know roughly, eg. dice rolls, only tell us their top bits, the ones their lowest
+// and highest mantissa agree on. The carry into the lowest of those comes from bits we don't know,
+// so it's guessed too, once per value, and from there up it's the same as for an exact value.
+// Calls that weren't observed tell us nothing, so the words either side of them are guessed
+// separately. Candidates are checked against the bounds of every observation.
+pub(crate) struct AdditiveSolver {
+  // The top bits of each output of the window, and the lowest bit of them we know.
+  known: Vec<(u64, usize)>,
+  // Smallest and largest mantissa of every observation, `None` if there are none.
+  bounds: Vec<Option<(u64, u64)>>,
+  // The equations at each bit, see `Level`.
+  levels: Vec<Level>,
+  // Every equation, and where the carries are worked out, in the order the search takes them.
+  steps: Vec<Step>,
+  // The first step at each bit, and then the number of steps.
+  level_steps: Vec<usize>,
+  elimination: Elimination,
+  // Right hand sides of the equations on the current branch.
+  values: EquationSet,
+  // Bit `k` of `carries[j]` is the carry into bit `j` of output `k`.
+  carries: Vec<u64>,
+  // Carries and words' bits the search guesses, rather than checks against the equations.
+  guesses: usize,
+  // Unknowns the equations never pin down, on any branch.
+  free: Vec<usize>,
+  // States that fit so far, and how many we're after.
+  candidates: Vec<(u64, u64)>,
+  limit: usize,
+}
+
+// What we know at one bit. Each output whose bit we know links the words either side of it, as
+// `S[k] ^ S[k + 1]`, and the lowest word of every run of linked words is guessed, as `S[k]`.
+// Each is there with the index of its equation.
+struct Level {
+  links: Vec<(usize, usize)>,
+  roots: Vec<(usize, usize)>,
+}
+
+// What the search does next. Equations that are the sum of earlier ones come with those, as
+// `Reduction::Dependent`, and are checked against them, or fill in a root without guessing.
+enum Step {
+  // Output `k`'s bit gives `S[k] ^ S[k + 1]`, given the carry into it.
+  Link {
+    bit: usize,
+    k: usize,
+    equation: usize,
+    reduction: Reduction,
+  },
+  // The same, at the lowest bit we know of an output, where the carry into it is guessed.
+  GuessedCarry {
+    bit: usize,
+    k: usize,
+    equation: usize,
+    reduction: Reduction,
+  },
+  // `S[k]`, guessed unless the equations so far already say what it is.
+  Root {
+    equation: usize,
+    reduction: Reduction,
+  },
+  // Every equation at the bit is in, the carries into the next one follow from them.
+  Carry {
+    bit: usize,
+  },
+}
+
+impl Step {
+  // Whether the search guesses at this step. When the equation's sources aren't all known yet
+  // (`defers`), dependent roots are guessed too, see `AdditiveSolver::meet_in_the_middle`.
+  fn is_guess(&self, defers: bool) -> bool {
+    return match self {
+      Step::GuessedCarry { .. } => true,
+      Step::Root { reduction, .. } => defers || matches!(reduction, Reduction::Pivot),
+      _ => false,
+    };
+  }
+
+  // The equation, and the earlier ones it's the sum of, if it's dependent.
+  fn dependent(&self) -> Option<(usize, &EquationSet)> {
+    return match self {
+      Step::Link {
+        equation,
+        reduction: Reduction::Dependent(sources),
+        ..
+      }
+      | Step::GuessedCarry {
+        equation,
+        reduction: Reduction::Dependent(sources),
+        ..
+      }
+      | Step::Root {
+        equation,
+        reduction: Reduction::Dependent(sources),
+      } => Some((*equation, sources)),
+      _ => None,
+    };
+  }
+}
+
+// Where one half of the search got to, when it meets in the middle.
+struct Half {
+  values: EquationSet,
+  // Carries into the bit the halves meet at.
+  middle: u64,
+  // Parities of the second half's dependent equations, over the right hand sides this half knows.
+  key: u64,
+  // Carries into the bit the second half stops at.
+  carries: u64,
+}
+
+impl AdditiveSolver {
+  const MANTISSA_BITS: usize = 53;
+  const MANTISSA_MASK: u64 = 0x1FFFFFFFFFFFFF;
+  // Known bits it takes to pin down the state, with a few to spare so fewer branches survive.
+  const TARGET_BITS: usize = 136;
+  // Windows stop growing past this many known bits, more exact values don't prune much more.
+  const MAX_WINDOW_BITS: usize = 12 * Self::MANTISSA_BITS;
+  const MAX_WINDOW_LEN: usize = 32;
+  // Every guess the equations can't check yet doubles the branches, this many take seconds.
+  const MAX_GUESSES: usize = 26;
+  #[cfg(feature = "z3")]
+  const TOO_FEW_BITS: &str = "the values give away too few bits each for the native solver, solve \
+     with z3 (`SolverBackend::Z3`, or `--solver z3`)";
+  #[cfg(not(feature = "z3"))]
+  const TOO_FEW_BITS: &str = "the values give away too few bits each for the native solver, add \
+     some exact values in a row, or build with the `z3` feature to solve with z3";
+
+  // Takes observations of any kind, `Unknown` for calls that weren't observed.
+  pub fn new(observations: &[Observation]) -> Self {
+    let bounds: Vec<Option<(u64, u64)>> = observations
+      .iter()
+      .map(|observation| {
+        return observation.bounds(Self::MANTISSA_BITS, |mantissa| {
+          return mantissa as f64 / (1u64 << 53) as f64;
+        });
+      })
+      .collect();
+    // The top bits the lowest and highest mantissa agree on, and the lowest of them.
+    let known: Vec<(u64, usize)> = bounds
+      .iter()
+      .map(|bounds| {
+        return match bounds {
+          Some((low, high)) if low == high => (*low, 0),
+          Some((low, high)) => (*low, 64 - (low ^ high).leading_zeros() as usize),
+          None => (0, Self::MANTISSA_BITS),
+        };
+      })
+      .collect();
+    let (start, len) = Self::window(&known);
+    let known = known[start..start + len].to_vec();
+
+    let mut state_0 = LinearWord::state_0();
+    let mut state_1 = LinearWord::state_1();
+    for _ in 0..start {
+      xor_shift_128_plus_linear(&mut state_0, &mut state_1);
+    }
+    let mut words = vec![state_1];
+    for _ in 0..len {
+      xor_shift_128_plus_linear(&mut state_0, &mut state_1);
+      words.push(state_1);
+    }
+
+    let mut levels = vec![];
+    let mut num_equations = 0;
+    for bit in 0..Self::MANTISSA_BITS {
+      let mut level = Level {
+        links: vec![],
+        roots: vec![],
+      };
+      for (k, &(_, lowest)) in known.iter().enumerate() {
+        if lowest <= bit {
+          let is_linked_below = k > 0 && known[k - 1].1 <= bit;
+          if !is_linked_below {
+            level.roots.push((k, 0));
+          }
+          level.links.push((k, 0));
+        }
+      }
+      for (_, equation) in level.links.iter_mut().chain(level.roots.iter_mut()) {
+        *equation = num_equations;
+        num_equations += 1;
+      }
+      levels.push(level);
+    }
+
+    let mut elimination = Elimination::new(num_equations);
+    let mut steps = Vec::with_capacity(num_equations + Self::MANTISSA_BITS);
+    let mut level_steps = Vec::with_capacity(Self::MANTISSA_BITS + 1);
+    let mut guesses = 0;
+    for (bit, level) in levels.iter().enumerate() {
+      level_steps.push(steps.len());
+      for &(k, equation) in &level.links {
+        let reduction = elimination.push(words[k].bit(bit) ^ words[k + 1].bit(bit));
+        if bit == known[k].1 && bit > 0 {
+          if matches!(reduction, Reduction::Pivot) {
+            guesses += 1;
+          }
+          steps.push(Step::GuessedCarry {
+            bit,
+            k,
+            equation,
+            reduction,
+          });
+        } else {
+          steps.push(Step::Link {
+            bit,
+            k,
+            equation,
+            reduction,
+          });
+        }
+      }
+      for &(k, equation) in &level.roots {
+        let reduction = elimination.push(words[k].bit(bit));
+        if matches!(reduction, Reduction::Pivot) {
+          guesses += 1;
+        }
+        steps.push(Step::Root {
+          equation,
+          reduction,
+        });
+      }
+      steps.push(Step::Carry { bit });
+    }
+    level_steps.push(steps.len());
+
+    return AdditiveSolver {
+      carries: vec![0; Self::MANTISSA_BITS + 1],
+      guesses,
+      known,
+      bounds,
+      levels,
+      steps,
+      level_steps,
+      values: EquationSet::new(num_equations),
+      free: elimination.free_variables(),
+      elimination,
+      candidates: vec![],
+      limit: 0,
+    };
+  }
+
+  // Static 'helper' method
+  // Where the observations to solve with start, and how many there are. Those that need the
+  // fewest guesses, out of those with enough bits known, and then the most bits known.
+  fn window(known: &[(u64, usize)]) -> (usize, usize) {
+    let mut best = (0, 0);
+    let mut best_score = (false, usize::MAX, 0);
+    for start in 0..known.len() {
+      let mut bits = 0;
+      for (len, &(_, lowest)) in known[start..].iter().take(Self::MAX_WINDOW_LEN).enumerate() {
+        bits += Self::MANTISSA_BITS - lowest;
+        // Short of enough bits, the guesses don't matter, it can't be solved anyway.
+        let is_enough = bits >= Self::TARGET_BITS;
+        let guesses = if is_enough {
+          Self::estimated_guesses(&known[start..=start + len])
+        } else {
+          0
+        };
+        let score = (is_enough, guesses, bits);
+        let is_better = (score.0 && !best_score.0)
+          || (score.0 == best_score.0
+            && (score.1 < best_score.1 || (score.1 == best_score.1 && score.2 > best_score.2)));
+        if is_better {
+          (best, best_score) = ((start, len + 1), score);
+        }
+        if bits >= Self::MAX_WINDOW_BITS {
+          break;
+        }
+      }
+    }
+    return best;
+  }
+
+  // Static 'helper' method
+  // Roughly how many bits are guessed before the equations pin down the state, so every guess
+  // after that can be checked. One per run of linked words at each bit, and one per carry.
+  fn estimated_guesses(known: &[(u64, usize)]) -> usize {
+    let (mut rank, mut guesses) = (0, 0);
+    for bit in 0..Self::MANTISSA_BITS {
+      let (mut links, mut roots) = (0, 0);
+      for (k, &(_, lowest)) in known.iter().enumerate() {
+        if lowest <= bit {
+          links += 1;
+          if k == 0 || known[k - 1].1 > bit {
+            roots += 1;
+          }
+          if rank < 128 && bit == lowest && lowest > 0 {
+            guesses += 1;
+          }
+        }
+      }
+      if rank < 128 {
+        guesses += roots;
+      }
+      rank += links + roots;
+    }
+    return guesses;
+  }
+
+  // Every concrete `(state_0, state_1)` after the last observation that fits, up to `limit` of them.
+  pub fn solve(mut self, limit: usize) -> Result<Vec<(u64, u64)>, InitError> {
+    if self.bounds.contains(&None) {
+      return Err(InitError::Unsat);
+    }
+    // Too few observations for every branch, there is no point searching. Past the longest
+    // window more of them won't help either, each gives away too little.
+    let unsupported = InitError::UnsupportedObservation(Self::TOO_FEW_BITS);
+    if self.levels.iter().all(|level| {
+      return level.links.is_empty();
+    }) || self.free.len() > MAX_FREE_VARIABLES
+    {
+      if self.known.len() == Self::MAX_WINDOW_LEN {
+        return Err(unsupported);
+      }
+      return Err(InitError::NotEnoughObservations);
+    }
+    if self.guesses > Self::MAX_GUESSES {
+      return Err(unsupported);
+    }
+    self.limit = limit;
+    match self.split() {
+      Some((middle, end)) => self.meet_in_the_middle(middle, end),
+      None => {
+        self.search(0, self.steps.len(), false, &mut |solver| {
+          return solver.collect();
+        });
+      }
+    }
+    if self.candidates.is_empty() {
+      return Err(InitError::Unsat);
+    }
+    return Ok(self.candidates);
+  }
+
+  // Where to meet in the middle, as the bits the second half starts and stops at, if that's
+  // quicker than searching straight through. Counts branches in powers of two: a guess doubles
+  // them, a check halves them, and of both halves only those whose carries and shared parities
+  // agree go on.
+  fn split(&self) -> Option<(usize, usize)> {
+    // At each bit, guesses and checks searching straight through, and guesses and parities to
+    // agree on for the second half.
+    let mut straight = vec![(0, 0); Self::MANTISSA_BITS];
+    let mut second = vec![(0, 0); Self::MANTISSA_BITS];
+    for (bit, counts) in straight.iter_mut().zip(second.iter_mut()).enumerate() {
+      for step in &self.steps[self.level_steps[bit]..self.level_steps[bit + 1]] {
+        let is_dependent = step.dependent().is_some();
+        counts.0.0 += step.is_guess(false) as i32;
+        counts.0.1 += (is_dependent && !matches!(step, Step::Root { .. })) as i32;
+        counts.1.0 += step.is_guess(true) as i32;
+        counts.1.1 += is_dependent as i32;
+      }
+    }
+    // Branches left after each bit searching straight through.
+    let mut branches = vec![0; Self::MANTISSA_BITS + 1];
+    for bit in 0..Self::MANTISSA_BITS {
+      let (guesses, checks) = straight[bit];
+      branches[bit + 1] = (branches[bit] + guesses - checks).max(0);
+    }
+    let peak_from = |bit: usize, start: i32| -> i32 {
+      let (mut count, mut peak) = (start, start);
+      for &(guesses, checks) in &straight[bit..] {
+        count = (count + guesses - checks).max(0);
+        peak = peak.max(count);
+      }
+      return peak;
+    };
+    let cost = |exponents: &[i32]| -> f64 {
+      return exponents
+        .iter()
+        .map(|&exponent| {
+          return 2f64.powi(exponent);
+        })
+        .sum();
+    };
+
+    let mut best = (cost(&[peak_from(0, 0)]) / 4.0, None);
+    for middle in 1..Self::MANTISSA_BITS {
+      let first = branches[..=middle].iter().copied().max().unwrap_or(0);
+      let carries = self.levels[middle - 1].links.len() as i32;
+      let (mut guesses, mut parities) = (carries, 0);
+      for end in middle + 1..=Self::MANTISSA_BITS {
+        guesses += second[end - 1].0;
+        parities = (parities + second[end - 1].1).min(64);
+        let meetings = (branches[middle] + guesses - carries - parities).max(0);
+        let total = cost(&[first, guesses, peak_from(end, meetings)]);
+        if total < best.0 {
+          best = (total, Some((middle, end)));
+        }
+      }
+    }
+    return best.1;
+  }
+
+  // Searches the bits below `middle` as usual, and separately the bits from there up to `end`,
+  // starting from every carry into `middle`. There, equations with sources on both sides can't be
+  // checked, so it guesses dependent roots too, and keeps the parity of what it knows of each.
+  // Pairs whose carries and parities agree fit every equation so far, and the search goes on from
+  // `end` for each. Each side guesses about half the bits, rather than one search all of them.
+  fn meet_in_the_middle(&mut self, middle: usize, end: usize) {
+    let (middle_step, end_step) = (self.level_steps[middle], self.level_steps[end]);
+    let checks: Vec<usize> = (middle_step..end_step)
+      .filter(|&i| {
+        return self.steps[i].dependent().is_some();
+      })
+      .collect();
+
+    let mut firsts = vec![];
+    self.search(0, middle_step, false, &mut |solver| {
+      firsts.push(Half {
+        values: solver.values.clone(),
+        middle: solver.carries[middle],
+        key: solver.key(&checks, false),
+        carries: 0,
+      });
+      return false;
+    });
+    let mut by_key: HashMap<(u64, u64), Vec<usize>> = HashMap::new();
+    for (i, first) in firsts.iter().enumerate() {
+      by_key.entry((first.middle, first.key)).or_default().push(i);
+    }
+
+    let links = self.levels[middle - 1]
+      .links
+      .iter()
+      .fold(0u64, |links, &(k, _)| {
+        return links | (1 << k);
+      });
+    let mut seconds = vec![];
+    let mut carries = 0u64;
+    loop {
+      self.values = EquationSet::new(self.values.capacity());
+      self.carries[middle] = carries;
+      self.search(middle_step, end_step, true, &mut |solver| {
+        seconds.push(Half {
+          values: solver.values.clone(),
+          middle: carries,
+          key: solver.key(&checks, true),
+          carries: solver.carries[end],
+        });
+        return false;
+      });
+      if carries == links {
+        break;
+      }
+      // The next set of carries, out of those into `middle`.
+      carries = (carries | !links).wrapping_add(1) & links;
+    }
+
+    for second in &seconds {
+      for &i in by_key
+        .get(&(second.middle, second.key))
+        .into_iter()
+        .flatten()
+      {
+        // The halves set different equations, so xor puts them together.
+        self.values = firsts[i].values.clone();
+        self.values.xor(&second.values);
+        let fits = checks.iter().all(|&check| {
+          return self.steps[check]
+            .dependent()
+            .is_none_or(|(equation, sources)| {
+              return sources.parity(&self.values) == self.values.get(equation);
+            });
+        });
+        if !fits {
+          continue;
+        }
+        self.carries[end] = second.carries;
+        let is_done = self.search(end_step, self.steps.len(), false, &mut |solver| {
+          return solver.collect();
+        });
+        if is_done {
+          return;
+        }
+      }
+    }
+  }
+
+  // Parities of the first 64 `checks` over the right hand sides set so far. The second half adds
+  // in each check's own, so they match the first half's where every equation holds.
+  fn key(&self, checks: &[usize], is_second: bool) -> u64 {
+    let mut key = 0;
+    for (j, &check) in checks.iter().take(64).enumerate() {
+      if let Some((equation, sources)) = self.steps[check].dependent() {
+        let parity = sources.parity(&self.values) ^ (is_second && self.values.get(equation));
+        key |= (parity as u64) << j;
+      }
+    }
+    return key;
+  }
+
+  // Takes the steps from `start` to `end` in order, guessing false first wherever it has to
+  // guess, and goes back to the latest guess still to be tried as true whenever an equation
+  // doesn't hold. Calls `reached` every time it gets to `end`, and returns true as soon as that
+  // does. A loop rather than recursion, this is most of the time spent solving.
+  fn search(
+    &mut self,
+    start: usize,
+    end: usize,
+    defers: bool,
+    reached: &mut impl FnMut(&mut Self) -> bool,
+  ) -> bool {
+    let mut pending = vec![];
+    let mut i = start;
+    loop {
+      let holds = if i == end {
+        if reached(self) {
+          return true;
+        }
+        false
+      } else {
+        if self.steps[i].is_guess(defers) {
+          pending.push(i);
+        }
+        self.take(i, false, defers)
+      };
+      if holds {
+        i += 1;
+        continue;
+      }
+      loop {
+        let Some(guessed) = pending.pop() else {
+          return false;
+        };
+        if self.take(guessed, true, defers) {
+          i = guessed + 1;
+          break;
+        }
+      }
+    }
+  }
+
+  // Takes step `i`, with `guess` for whatever it guesses. Returns false if an equation no longer
+  // holds. With `defers`, dependent equations are set like pivots and left for `key` to check.
+  fn take(&mut self, i: usize, guess: bool, defers: bool) -> bool {
+    let values = &mut self.values;
+    let carries = &mut self.carries;
+    return match &self.steps[i] {
+      Step::Link {
+        bit,
+        k,
+        equation,
+        reduction,
+      } => {
+        // Whatever the words' bits are, their xor is what the output's bit says, given the carry.
+        let value = ((self.known[*k].0 >> bit) ^ (carries[*bit] >> k)) & 1 == 1;
+        Self::assign(values, *equation, reduction, value, defers)
+      }
+      Step::GuessedCarry {
+        bit,
+        k,
+        equation,
+        reduction,
+      } => {
+        carries[*bit] = (carries[*bit] & !(1 << k)) | ((guess as u64) << k);
+        let value = ((self.known[*k].0 >> bit) & 1 == 1) ^ guess;
+        Self::assign(values, *equation, reduction, value, defers)
+      }
+      Step::Root {
+        equation,
+        reduction,
+      } => {
+        let value = match reduction {
+          Reduction::Dependent(sources) if !defers => sources.parity(values),
+          _ => guess,
+        };
+        values.set(*equation, value);
+        true
+      }
+      Step::Carry { bit } => {
+        // The words' bits, from the root of each run of linked words up, and the carries out.
+        let level = &self.levels[*bit];
+        let mut word_bits = 0u64;
+        for &(k, equation) in &level.roots {
+          word_bits |= (values.get(equation) as u64) << k;
+        }
+        let mut next = 0u64;
+        for &(k, equation) in &level.links {
+          let (low, carry) = ((word_bits >> k) & 1, (carries[*bit] >> k) & 1);
+          let high = low ^ values.get(equation) as u64;
+          word_bits |= high << (k + 1);
+          next |= ((low & high) | (carry & (low ^ high))) << k;
+        }
+        carries[*bit + 1] = next;
+        true
+      }
+    };
+  }
+
+  // Static 'helper' method
+  // Records the right hand side of an equation, returns false if it contradicts earlier ones.
+  fn assign(
+    values: &mut EquationSet,
+    equation: usize,
+    reduction: &Reduction,
+    value: bool,
+    defers: bool,
+  ) -> bool {
+    values.set(equation, value);
+    return match reduction {
+      Reduction::Dependent(sources) if !defers => sources.parity(values) == value,
+      _ => true,
+    };
+  }
+
+  // Every state the equations leave, with the free unknowns set every way, that fits every
+  // observation. Returns true once there are enough candidates to stop.
+  fn collect(&mut self) -> bool {
+    for free in free_assignments(&self.free) {
+      let unknowns = self.elimination.solve_with(&self.values, free);
+      if let Some(state) = self.replay(unknowns) {
+        self.candidates.push(state);
+      }
+      if self.candidates.len() >= self.limit {
+        return true;
+      }
+    }
+    return false;
+  }
+
+  // Runs a candidate over every observation, including the ones that were not solved for.
+  fn replay(&self, unknowns: u128) -> Option<(u64, u64)> {
+    let mut state_0 = unknowns as u64;
+    let mut state_1 = (unknowns >> 64) as u64;
+
+    for &(low, high) in self.bounds.iter().flatten() {
+      let mantissa = xor_shift_128_plus_concrete(&mut state_0, &mut state_1) & Self::MANTISSA_MASK;
+      if !(low..=high).contains(&mantissa) {
+        return None;
+      }
+    }
+
+    return Some((state_0, state_1));
+  }
+}
+
+fn xor_shift_128_plus_concrete(state_0: &mut u64, state_1: &mut u64) -> u64 {
+  let mut s1 = *state_0;
+  let s0 = *state_1;
+  *state_0 = s0;
+  s1 = s1 ^ s1 << 23;
+  *state_1 = s1 ^ s0 ^ (s1 >> 17) ^ (s0 >> 26);
+  return state_1.wrapping_add(s0);
+}
@@ -0,0 +1,247 @@
+use clap::ValueEnum;
+use std::fmt::{Display, Formatter, Result};
+
+// Chrome has used xorshift128+ since V8 4.9, which shipped with Chrome 49. Before that, it used
+// MWC1616, which `ChromePredictor` picks for the older versions.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ChromeMajorVersion {
+  V30 = 30,
+  V31 = 31,
+  V32 = 32,
+  V33 = 33,
+  V34 = 34,
+  V35 = 35,
+  V36 = 36,
+  V37 = 37,
+  V38 = 38,
+  V39 = 39,
+  V40 = 40,
+  V41 = 41,
+  V42 = 42,
+  V43 = 43,
+  V44 = 44,
+  V45 = 45,
+  V46 = 46,
+  V47 = 47,
+  V48 = 48,
+  V49 = 49,
+  V50 = 50,
+  V51 = 51,
+  V52 = 52,
+  V53 = 53,
+  V54 = 54,
+  V55 = 55,
+  V56 = 56,
+  V57 = 57,
+  V58 = 58,
+  V59 = 59,
+  V60 = 60,
+  V61 = 61,
+  V62 = 62,
+  V63 = 63,
+  V64 = 64,
+  V65 = 65,
+  V66 = 66,
+  V67 = 67,
+  V68 = 68,
+  V69 = 69,
+  V70 = 70,
+  V71 = 71,
+  V72 = 72,
+  V73 = 73,
+  V74 = 74,
+  V75 = 75,
+  V76 = 76,
+  V77 = 77,
+  V78 = 78,
+  V79 = 79,
+  V80 = 80,
+  V81 = 81,
+  V82 = 82,
+  V83 = 83,
+  V84 = 84,
+  V85 = 85,
+  V86 = 86,
+  V87 = 87,
+  V88 = 88,
+  V89 = 89,
+  V90 = 90,
+  V91 = 91,
+  V92 = 92,
+  V93 = 93,
+  V94 = 94,
+  V95 = 95,
+  V96 = 96,
+  V97 = 97,
+  V98 = 98,
+  V99 = 99,
+  V100 = 100,
+  V101 = 101,
+  V102 = 102,
+  V103 = 103,
+  V104 = 104,
+  V105 = 105,
+  V106 = 106,
+  V107 = 107,
+  V108 = 108,
+  V109 = 109,
+  V110 = 110,
+  V111 = 111,
+  V112 = 112,
+  V113 = 113,
+  V114 = 114,
+  V115 = 115,
+  V116 = 116,
+  V117 = 117,
+  V118 = 118,
+  V119 = 119,
+  V120 = 120,
+  V121 = 121,
+  V122 = 122,
+  V123 = 123,
+  V124 = 124,
+  V125 = 125,
+  V126 = 126,
+  V127 = 127,
+  V128 = 128,
+  V129 = 129,
+  V130 = 130,
+  V131 = 131,
+  V132 = 132,
+  V133 = 133,
+  V134 = 134,
+  V135 = 135,
+  V136 = 136,
+  V137 = 137,
+  V138 = 138,
+  V139 = 139,
+  V140 = 140,
+  V141 = 141,
+}
+
+impl ChromeMajorVersion {
+  #[allow(dead_code, clippy::implicit_return)]
+  pub fn from_u8(value: u8) -> Option<Self> {
+    match value {
+      30 => Some(Self::V30),
+      31 => Some(Self::V31),
+      32 => Some(Self::V32),
+      33 => Some(Self::V33),
+      34 => Some(Self::V34),
+      35 => Some(Self::V35),
+      36 => Some(Self::V36),
+      37 => Some(Self::V37),
+      38 => Some(Self::V38),
+      39 => Some(Self::V39),
+      40 => Some(Self::V40),
+      41 => Some(Self::V41),
+      42 => Some(Self::V42),
+      43 => Some(Self::V43),
+      44 => Some(Self::V44),
+      45 => Some(Self::V45),
+      46 => Some(Self::V46),
+      47 => Some(Self::V47),
+      48 => Some(Self::V48),
+      49 => Some(Self::V49),
+      50 => Some(Self::V50),
+      51 => Some(Self::V51),
+      52 => Some(Self::V52),
+      53 => Some(Self::V53),
+      54 => Some(Self::V54),
+      55 => Some(Self::V55),
+      56 => Some(Self::V56),
+      57 => Some(Self::V57),
+      58 => Some(Self::V58),
+      59 => Some(Self::V59),
+      60 => Some(Self::V60),
+      61 => Some(Self::V61),
+      62 => Some(Self::V62),
+      63 => Some(Self::V63),
+      64 => Some(Self::V64),
+      65 => Some(Self::V65),
+      66 => Some(Self::V66),
+      67 => Some(Self::V67),
+      68 => Some(Self::V68),
+      69 => Some(Self::V69),
+      70 => Some(Self::V70),
+      71 => Some(Self::V71),
+      72 => Some(Self::V72),
+      73 => Some(Self::V73),
+      74 => Some(Self::V74),
+      75 => Some(Self::V75),
+      76 => Some(Self::V76),
+      77 => Some(Self::V77),
+      78 => Some(Self::V78),
+      79 => Some(Self::V79),
+      80 => Some(Self::V80),
+      81 => Some(Self::V81),
+      82 => Some(Self::V82),
+      83 => Some(Self::V83),
+      84 => Some(Self::V84),
+      85 => Some(Self::V85),
+      86 => Some(Self::V86),
+      87 => Some(Self::V87),
+      88 => Some(Self::V88),
+      89 => Some(Self::V89),
+      90 => Some(Self::V90),
+      91 => Some(Self::V91),
+      92 => Some(Self::V92),
+      93 => Some(Self::V93),
+      94 => Some(Self::V94),
+      95 => Some(Self::V95),
+      96 => Some(Self::V96),
+      97 => Some(Self::V97),
+      98 => Some(Self::V98),
+      99 => Some(Self::V99),
+      100 => Some(Self::V100),
+      101 => Some(Self::V101),
+      102 => Some(Self::V102),
+      103 => Some(Self::V103),
+      104 => Some(Self::V104),
+      105 => Some(Self::V105),
+      106 => Some(Self::V106),
+      107 => Some(Self::V107),
+      108 => Some(Self::V108),
+      109 => Some(Self::V109),
+      110 => Some(Self::V110),
+      111 => Some(Self::V111),
+      112 => Some(Self::V112),
+      113 => Some(Self::V113),
+      114 => Some(Self::V114),
+      115 => Some(Self::V115),
+      116 => Some(Self::V116),
+      117 => Some(Self::V117),
+      118 => Some(Self::V118),
+      119 => Some(Self::V119),
+      120 => Some(Self::V120),
+      121 => Some(Self::V121),
+      122 => Some(Self::V122),
+      123 => Some(Self::V123),
+      124 => Some(Self::V124),
+      125 => Some(Self::V125),
+      126 => Some(Self::V126),
+      127 => Some(Self::V127),
+      128 => Some(Self::V128),
+      129 => Some(Self::V129),
+      130 => Some(Self::V130),
+      131 => Some(Self::V131),
+      132 => Some(Self::V132),
+      133 => Some(Self::V133),
+      134 => Some(Self::V134),
+      135 => Some(Self::V135),
+      136 => Some(Self::V136),
+      137 => Some(Self::V137),
+      138 => Some(Self::V138),
+      139 => Some(Self::V139),
+      140 => Some(Self::V140),
+      141 => Some(Self::V141),
+      _ => None,
+    }
+  }
+}
+
+impl Display for ChromeMajorVersion {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    return write!(f, "v{}", *self as u8);
+  }
+}
@@ -1,143 +1,31 @@
-use crate::{Predictor, errors::InitError};
-use std::error::Error;
-use z3::{self, Config, Context, SatResult, Solver, ast::*};
-
+use crate::{
+  V8Version,
+  v8_predictor::{V8Engine, v8_predictor},
+};
+
+// Takes a `ChromeMajorVersion`, or the `V8Version` from `Runtime::Chrome.v8_version("133.0.6943.53")`.
+// `from_seed` predicts a run started with `--js-flags=--random-seed=N`.
+#[derive(Clone)]
 pub struct ChromePredictor {
-  sequence: Vec<f64>,
-  internal_sequence: Vec<f64>,
-  is_solved: bool,
-  conc_state_0: u64,
-  conc_state_1: u64,
-}
-
-impl Predictor for ChromePredictor {
-  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
-    self.solve_symbolic_state()?; // if solving fails, error is returned early
-    let v = self.xor_shift_128_plus_concrete();
-    return Ok(self.to_double(v));
-  }
+  v8: V8Engine,
+  // Exported with the state, `None` if it was picked up from a state that didn't say.
+  version: Option<V8Version>,
 }
 
-impl ChromePredictor {
-  const SS_0_STR: &str = "sym_state_0";
-  const SS_1_STR: &str = "sym_state_1";
-
-  pub fn new(seq: Vec<f64>) -> Self {
-    let mut iseq = seq.clone();
-    iseq.reverse();
-
-    return ChromePredictor {
-      internal_sequence: iseq,
-      sequence: seq,
-      is_solved: false,
-      conc_state_0: 0,
-      conc_state_1: 0,
-    };
-  }
-
-  #[allow(dead_code)]
-  pub fn sequence(&self) -> &[f64] {
-    return &self.sequence;
-  }
-
-  // So consumers don't have to import the Predictor trait as well as the struct.
-  pub fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
-    return <Self as Predictor>::predict_next(self);
-  }
-
-  // Performs XORShift in reverse.
-  fn xor_shift_128_plus_concrete(&mut self) -> u64 {
-    let result = self.conc_state_0;
-    let temp1 = self.conc_state_0;
-    let mut temp0 = self.conc_state_1 ^ (self.conc_state_0 >> 26);
-    temp0 ^= self.conc_state_0;
-    temp0 ^= (temp0 >> 17) ^ (temp0 >> 34) ^ (temp0 >> 51);
-    temp0 ^= (temp0 << 23) ^ (temp0 << 46);
-    self.conc_state_0 = temp0;
-    self.conc_state_1 = temp1;
-    return result;
-  }
-
-  fn to_double(&self, value: u64) -> f64 {
-    return (value >> 11) as f64 / (1u64 << 53) as f64;
-  }
-
-  fn solve_symbolic_state(&mut self) -> Result<(), InitError> {
-    if self.is_solved {
-      return Ok(());
-    }
-
-    let config = Config::new();
-    let context = Context::new(&config);
-    let solver = Solver::new(&context);
-
-    let mut sym_state_0 = BV::new_const(&context, Self::SS_0_STR, 64);
-    let mut sym_state_1 = BV::new_const(&context, Self::SS_1_STR, 64);
-
-    for &observed in &self.internal_sequence {
-      Self::xor_shift_128_plus_symbolic(&context, &mut sym_state_0, &mut sym_state_1);
-      Self::constrain_mantissa(observed, &context, &solver, &sym_state_0);
-    }
-
-    if solver.check() != SatResult::Sat {
-      return Err(InitError::Unsat);
-    }
-
-    let model = solver.get_model().ok_or(InitError::MissingModel)?;
-
-    self.conc_state_0 = model
-      .eval(&sym_state_0, true)
-      .ok_or(InitError::EvalFailed(Self::SS_0_STR))?
-      .as_u64()
-      .ok_or(InitError::ConvertFailed(Self::SS_0_STR))?;
-
-    self.conc_state_1 = model
-      .eval(&sym_state_1, true)
-      .ok_or(InitError::EvalFailed(Self::SS_1_STR))?
-      .as_u64()
-      .ok_or(InitError::ConvertFailed(Self::SS_1_STR))?;
-
-    for _ in 0..self.internal_sequence.len() {
-      self.xor_shift_128_plus_concrete();
-    }
-
-    self.is_solved = true;
-    return Ok(());
-  }
-
-  // Static 'helper' method
-  fn xor_shift_128_plus_symbolic<'a>(
-    context: &'a Context,
-    state_0: &mut BV<'a>,
-    state_1: &mut BV<'a>,
-  ) {
-    let state_0_shifted_left = state_0.bvshl(&BV::from_u64(context, 23, 64));
-    let mut s1 = &*state_0 ^ state_0_shifted_left;
-    let s1_shifted_right = s1.bvlshr(&BV::from_u64(context, 17, 64));
-
-    s1 ^= s1_shifted_right;
-    s1 ^= state_1.clone();
-    s1 ^= state_1.bvlshr(&BV::from_u64(context, 26, 64));
-
-    std::mem::swap(state_0, state_1);
-    *state_1 = s1;
-  }
-
-  // Static 'helper' method
-  fn constrain_mantissa(value: f64, context: &Context, solver: &Solver, state_0: &BV) {
-    // Recover mantissa
-    let mantissa = (value * (1u64 << 53) as f64) as u64;
-    // Add mantissa constraint
-    solver.assert(
-      &state_0
-        .bvlshr(&BV::from_u64(context, 11, 64))
-        ._eq(&BV::from_u64(context, mantissa, 64)),
-    );
-  }
-}
+v8_predictor!(ChromePredictor);
 
 #[cfg(test)]
 mod tests {
+  #[cfg(feature = "z3")]
+  use crate::SolverBackend;
+  use crate::{
+    ChromeMajorVersion, ChromePredictor, V8Variant,
+    errors::PredictionLimitError,
+    v8_predictor::{
+      XorShiftVariant,
+      test_values::{TEST_STATE, v8_values, v8_values_from},
+    },
+  };
   use std::error::Error;
 
   #[test]
@@ -177,7 +65,7 @@ mod tests {
       0.5799453712253447,
     ];
 
-    let mut cp = crate::ChromePredictor::new(sequence);
+    let mut cp = ChromePredictor::new(ChromeMajorVersion::V138, sequence);
     let mut predictions = vec![];
 
     for _ in 0..expected.len() {
@@ -188,4 +76,131 @@ mod tests {
     assert_eq!(predictions, expected);
     return Ok(());
   }
+
+  #[test]
+  fn recovers_values_before_sequence() -> Result<(), Box<dyn Error>> {
+    let values = v8_values(128);
+
+    // A sequence that fits inside one pool is assumed to start at its top, so going back
+    // crosses into the previous pool straight away.
+    let mut cp = ChromePredictor::new(ChromeMajorVersion::V138, values[64..69].to_vec());
+
+    assert_eq!(cp.predict_previous()?, values[63]);
+    assert_eq!(cp.history(70)?[7..], values[..63]);
+    return Ok(());
+  }
+
+  #[test]
+  fn uses_52_bit_conversion_before_chrome_133() -> Result<(), Box<dyn Error>> {
+    let values = v8_values_from(TEST_STATE, XorShiftVariant::Mantissa52, 25);
+
+    let mut old = ChromePredictor::new(ChromeMajorVersion::V132, values[..5].to_vec());
+    for expected in &values[5..25] {
+      assert_eq!(old.predict_next()?, *expected);
+    }
+
+    let mut new = ChromePredictor::new(ChromeMajorVersion::V133, values[..5].to_vec());
+    assert!(new.predict_next().is_err());
+    return Ok(());
+  }
+
+  #[test]
+  fn solves_sequence_starting_inside_pool() -> Result<(), Box<dyn Error>> {
+    let values = v8_values(128);
+
+    // Other code used up 61 values before we started observing, so we straddle the refill.
+    let sequence = values[61..67].to_vec();
+
+    let mut cp = ChromePredictor::new(ChromeMajorVersion::V138, sequence);
+
+    assert_eq!(cp.pool_offset()?, 61);
+    assert_eq!(cp.pool_index()?, 3);
+    for expected in &values[67..77] {
+      assert_eq!(cp.predict_next()?, *expected);
+    }
+    assert_eq!(cp.history(8)?, values[53..61]);
+    return Ok(());
+  }
+
+  #[test]
+  fn stops_at_end_of_pool_unless_pool_aware() -> Result<(), Box<dyn Error>> {
+    let sequence = vec![
+      0.32096095967729477,
+      0.3940071672626849,
+      0.3363374923027722,
+      0.7518761096243554,
+      0.44201420586496387,
+    ];
+
+    let mut cp = ChromePredictor::new(ChromeMajorVersion::V138, sequence.clone());
+    for _ in sequence.len()..ChromePredictor::MAX_NUM_PREDICTIONS as usize {
+      cp.predict_next()?;
+    }
+    let err = cp.predict_next().unwrap_err();
+    assert!(err.downcast_ref::<PredictionLimitError>().is_some());
+
+    let mut pool_aware = ChromePredictor::new(ChromeMajorVersion::V138, sequence);
+    pool_aware.set_pool_aware(true);
+    for _ in 0..100 {
+      pool_aware.predict_next()?;
+    }
+    assert_eq!(pool_aware.pool_index()?, 41);
+    return Ok(());
+  }
+
+  #[cfg(feature = "z3")]
+  #[test]
+  fn native_and_z3_solvers_agree() -> Result<(), Box<dyn Error>> {
+    let sequence = vec![
+      0.32096095967729477,
+      0.3940071672626849,
+      0.3363374923027722,
+      0.7518761096243554,
+      0.44201420586496387,
+    ];
+
+    let mut native = ChromePredictor::with_solver(
+      ChromeMajorVersion::V138,
+      sequence.clone(),
+      SolverBackend::Native,
+    );
+    let mut z3 =
+      ChromePredictor::with_solver(ChromeMajorVersion::V138, sequence, SolverBackend::Z3);
+
+    for _ in 0..25 {
+      assert_eq!(native.predict_next()?, z3.predict_next()?);
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn uses_mwc1616_before_chrome_49() -> Result<(), Box<dyn Error>> {
+    // Generated with V8 3.x's C++ `random_base` (MWC1616, multipliers 36969 and 18273).
+    let values = [
+      0.8164385361596942,
+      0.3798071313649416,
+      0.52775416104123,
+      0.2520046036224812,
+      0.4450977323576808,
+      0.438436983153224,
+      0.5988072748295963,
+      0.3088930386584252,
+      0.4580697596538812,
+      0.5214740643277764,
+      0.450158404186368,
+      0.8723744705785066,
+      0.5392520595341921,
+      0.8628884493373334,
+      0.2820720875170082,
+      0.9154236516915262,
+    ];
+
+    let mut cp = ChromePredictor::new(ChromeMajorVersion::V30, values[2..6].to_vec());
+    assert_eq!(cp.variant(), V8Variant::Mwc1616);
+    for expected in &values[6..] {
+      assert_eq!(cp.predict_next()?, *expected);
+    }
+    assert_eq!(cp.history(2)?, values[..2]);
+    return Ok(());
+  }
 }
@@ -0,0 +1,185 @@
+use crate::{
+  Observation, Predictor, SolverBackend, V8Variant,
+  additive_engine::AdditiveEngine,
+  errors::InitError,
+  mwc1616_predictor::Mwc1616Predictor,
+  v8_predictor::{V8Engine, V8Predictor, XorShiftVariant},
+};
+use std::{
+  error::Error,
+  fmt::{Display, Formatter, Result},
+  thread,
+};
+
+// Every generator we know how to predict. Runtimes whose output can't be told apart share one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Engine {
+  // Node, Chrome, Deno and Electron. Which variant depends on the V8 version.
+  V8(V8Variant),
+  // Firefox and Safari run the same xorshift128+, and convert it to a double the same way.
+  FirefoxSafari,
+}
+
+impl Engine {
+  pub const ALL: [Engine; 4] = [
+    Engine::V8(V8Variant::Mwc1616),
+    Engine::V8(V8Variant::Mantissa52),
+    Engine::V8(V8Variant::Mantissa53),
+    Engine::FirefoxSafari,
+  ];
+
+  // Which runtimes, and which of their versions, generate output like this.
+  pub fn environments(&self) -> &'static str {
+    return match self {
+      Engine::V8(V8Variant::Mwc1616) => "Node.js 5 and older, Chrome 48 and older",
+      Engine::V8(V8Variant::Mantissa52) => {
+        "Node.js 6 to 23, Chrome 49 to 132, Deno 2.1 and older, Electron 1 to 34"
+      }
+      Engine::V8(V8Variant::Mantissa53) => {
+        "Node.js 24 and newer, Chrome 133 and newer, Deno 2.2 and newer, Electron 35 and newer"
+      }
+      Engine::FirefoxSafari => "Firefox, Safari",
+    };
+  }
+
+  // A predictor for this engine that is already solved for `observations`. Callers try many
+  // sequences, most of which won't fit, so no time is spent working out why one doesn't.
+  pub(crate) fn solve(
+    self,
+    observations: Vec<Observation>,
+    solver: SolverBackend,
+    allows_ambiguity: bool,
+  ) -> std::result::Result<Box<dyn Predictor + Send>, Box<dyn Error>> {
+    return match self {
+      Engine::V8(variant) => {
+        let mut v8 = V8Engine::new(observations, variant, solver);
+        v8.set_allow_ambiguous(allows_ambiguity);
+        v8.set_diagnose_unsat(false);
+        v8.solve_symbolic_state()?;
+        Ok(Box::new(v8))
+      }
+      Engine::FirefoxSafari => {
+        let mut additive = AdditiveEngine::from_observations(observations, solver);
+        additive.set_allow_ambiguous(allows_ambiguity);
+        additive.set_diagnose_unsat(false);
+        additive.solve_symbolic_state()?;
+        Ok(Box::new(additive))
+      }
+    };
+  }
+
+  // Indices of `sequence` in each of up to `streams` interleaved streams this engine generated,
+  // see `untangle::untangle`.
+  pub(crate) fn untangle(
+    self,
+    sequence: &[f64],
+    streams: usize,
+  ) -> std::result::Result<Vec<Vec<usize>>, InitError> {
+    return match self {
+      Engine::V8(variant) => match XorShiftVariant::of(variant) {
+        Some(variant) => V8Predictor::untangle(sequence, variant, streams),
+        None => Mwc1616Predictor::untangle(sequence, streams),
+      },
+      Engine::FirefoxSafari => AdditiveEngine::untangle_members(sequence, streams),
+    };
+  }
+}
+
+impl Display for Engine {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    #[allow(clippy::implicit_return)]
+    match self {
+      Engine::V8(V8Variant::Mwc1616) => write!(f, "V8 MWC1616"),
+      Engine::V8(V8Variant::Mantissa52) => write!(f, "V8 xorshift128+ (52 bit)"),
+      Engine::V8(V8Variant::Mantissa53) => write!(f, "V8 xorshift128+ (53 bit)"),
+      Engine::FirefoxSafari => write!(f, "xorshift128+ (Firefox/Safari)"),
+    }
+  }
+}
+
+// An engine that can reproduce the sequence, and a predictor that is already solved for it.
+pub struct Detection {
+  pub engine: Engine,
+  pub predictor: Box<dyn Predictor + Send>,
+}
+
+// Every engine that can reproduce `sequence`, in the order of `Engine::ALL`. More than one means
+// the sequence is too short to tell them apart, none means it came from somewhere we don't know.
+// Takes exact values, or `Observation`s. An engine counts even if more than one of its states fit,
+// so check `candidate_states` before trusting the predictor.
+pub fn detect<O: Into<Observation> + Copy>(sequence: &[O]) -> Vec<Detection> {
+  return detect_with_solver(sequence, SolverBackend::default());
+}
+
+pub fn detect_with_solver<O: Into<Observation> + Copy>(
+  sequence: &[O],
+  solver: SolverBackend,
+) -> Vec<Detection> {
+  let observations: Vec<Observation> = sequence.iter().copied().map(Into::into).collect();
+  // Engines don't depend on each other, and some take a while to rule out, so try them all at once.
+  return thread::scope(|scope| {
+    let handles: Vec<_> = Engine::ALL
+      .iter()
+      .map(|&engine| {
+        let observations = observations.clone();
+        return scope.spawn(move || {
+          let predictor = engine.solve(observations, solver, true).ok()?;
+          return Some(Detection { engine, predictor });
+        });
+      })
+      .collect();
+
+    return handles
+      .into_iter()
+      .filter_map(|handle| {
+        return handle.join().ok().flatten();
+      })
+      .collect();
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{Engine, V8Variant, detect};
+  use std::error::Error;
+
+  #[test]
+  fn detects_node_v24() -> Result<(), Box<dyn Error>> {
+    let sequence = [
+      0.01800425609760259,
+      0.19267361208155598,
+      0.9892770985784053,
+      0.49553307275603264,
+      0.7362624704291061,
+    ];
+
+    let mut detections = detect(&sequence);
+    assert_eq!(detections.len(), 1);
+    assert_eq!(detections[0].engine, Engine::V8(V8Variant::Mantissa53));
+    assert_eq!(detections[0].predictor.predict_next()?, 0.8664993194151147);
+    return Ok(());
+  }
+
+  #[test]
+  fn detects_firefox_and_safari_as_one() -> Result<(), Box<dyn Error>> {
+    let sequence = [
+      0.5865531271930553,
+      0.5541046114391099,
+      0.21640895758393563,
+      0.7795614489825657,
+      0.45436917267245447,
+    ];
+
+    let mut detections = detect(&sequence);
+    assert_eq!(detections.len(), 1);
+    assert_eq!(detections[0].engine, Engine::FirefoxSafari);
+    assert_eq!(detections[0].predictor.predict_next()?, 0.23093540482617203);
+    return Ok(());
+  }
+
+  #[test]
+  fn detects_nothing_for_made_up_numbers() {
+    assert!(detect(&[0.1, 0.2, 0.3, 0.4, 0.5]).is_empty());
+  }
+}
@@ -0,0 +1,142 @@
+use crate::{Observation, errors::InitError};
+#[cfg(feature = "z3")]
+use z3::{Context, SatResult, Solver, ast::Bool};
+
+// Works out why no state fits `observations`, for `InitError::Inconsistent`. `solve` counts the
+// states that fit (up to 2, without diagnosing), for observations with some replaced by `Unknown`.
+// `is_possible` says if the engine returns any value matching an observation at all. `core` narrows
+// down where to look, eg. to the unsat core z3 found.
+//
+// An observation nothing matches is to blame on its own, and so is one the rest fit without.
+// Otherwise every observation of the core is dropped in turn, and only put back if something fits
+// without it. What's left can't all be right, but without any one of them the rest could be. Too
+// few observations to solve don't say either way, so they don't get one put back, and if that
+// leaves nothing, every observation is to blame.
+pub(crate) fn diagnose(
+  observations: &[Observation],
+  core: Option<Vec<usize>>,
+  is_possible: impl Fn(&Observation) -> bool,
+  solve: impl Fn(&[Observation]) -> Result<usize, InitError>,
+) -> InitError {
+  let without = |i: usize| {
+    let mut dropped = observations.to_vec();
+    dropped[i] = Observation::Unknown;
+    return dropped;
+  };
+  // How many states fit without each of `indices`, if any do. The last value is the likeliest one
+  // to have been pasted from somewhere else, so that's the culprit if more than one could be.
+  let drop_each = |indices: &[usize]| {
+    let counts: Vec<(usize, Option<usize>)> = indices
+      .iter()
+      .map(|&i| {
+        return (i, solve(trimmed(&without(i))).ok());
+      })
+      .collect();
+    let culprit = counts.iter().rev().find_map(|&(i, count)| {
+      return (count == Some(1)).then_some(i);
+    });
+    return (counts, culprit);
+  };
+
+  let impossible: Vec<usize> = (0..observations.len())
+    .filter(|&i| {
+      return !is_possible(&observations[i]);
+    })
+    .collect();
+  if !impossible.is_empty() {
+    let (_, culprit) = drop_each(&impossible);
+    return InitError::Inconsistent {
+      indices: impossible,
+      culprit,
+    };
+  }
+
+  let core: Vec<usize> = core
+    .unwrap_or_else(|| {
+      return (0..observations.len()).collect();
+    })
+    .into_iter()
+    .filter(|&i| {
+      return observations[i] != Observation::Unknown;
+    })
+    .collect();
+
+  // A single value from somewhere else is the likeliest, so first look for values the rest fit
+  // without.
+  let (counts, culprit) = drop_each(&core);
+  let mut indices: Vec<usize> = counts
+    .into_iter()
+    .filter_map(|(i, count)| {
+      return count.map(|_| {
+        return i;
+      });
+    })
+    .collect();
+
+  if indices.is_empty() {
+    // Outside the core, nothing needs to be dropped to keep it unsat.
+    let mut kept: Vec<Observation> = vec![Observation::Unknown; observations.len()];
+    for &i in &core {
+      kept[i] = observations[i];
+    }
+    for &i in &core {
+      kept[i] = Observation::Unknown;
+      if solve(trimmed(&kept)).is_ok() {
+        kept[i] = observations[i];
+        indices.push(i);
+      }
+    }
+  }
+  if indices.is_empty() {
+    indices = core;
+  }
+  return InitError::Inconsistent { indices, culprit };
+}
+
+// Calls before the first observation and after the last one don't tell us anything, but let V8's
+// cache fit more placements, and some solvers search from the first observation, so leave them out.
+fn trimmed(observations: &[Observation]) -> &[Observation] {
+  let is_known = |observation: &Observation| {
+    return *observation != Observation::Unknown;
+  };
+  let Some(first) = observations.iter().position(is_known) else {
+    return &[];
+  };
+  let last = observations.iter().rposition(is_known).unwrap_or(first);
+  return &observations[first..=last];
+}
+
+// Indices of the `constraints` (one per observation) that z3 found can't all hold, if they can't.
+// Each one only holds while its own literal is assumed, so the core says which were needed.
+#[cfg(feature = "z3")]
+pub(crate) fn unsat_core<'a>(
+  context: &'a Context,
+  solver: &Solver<'a>,
+  constraints: &[Bool<'a>],
+) -> Option<Vec<usize>> {
+  let literals: Vec<Bool> = (0..constraints.len())
+    .map(|i| {
+      return Bool::new_const(context, format!("observation_{i}"));
+    })
+    .collect();
+
+  solver.push();
+  for (literal, constraint) in literals.iter().zip(constraints) {
+    solver.assert(&literal.implies(constraint));
+  }
+  let core = match solver.check_assumptions(&literals) {
+    SatResult::Unsat => {
+      let core = solver.get_unsat_core();
+      Some(
+        (0..literals.len())
+          .filter(|&i| {
+            return core.contains(&literals[i]);
+          })
+          .collect(),
+      )
+    }
+    _ => None,
+  };
+  solver.pop(1);
+  return core;
+}
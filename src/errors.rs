@@ -1,3 +1,4 @@
+use crate::{Engine, PredictorState};
 use std::{
   error::Error,
   fmt::{Display, Formatter, Result},
@@ -7,6 +8,31 @@ use std::{
 #[derive(Debug)]
 pub enum InitError {
   Unsat,
+  NotEnoughObservations,
+  // More than one state fits the observations, so predictions could come from the wrong one.
+  Ambiguous,
+  // No state fits, because the observations at `indices` can't all be right. Dropping the one at
+  // `culprit`, if there is one, leaves exactly one state that fits the rest.
+  Inconsistent {
+    indices: Vec<usize>,
+    culprit: Option<usize>,
+  },
+  UnsupportedObservation(&'static str),
+  NoGapFits(usize),
+  // Both of these combinations of skipped calls make the fragments fit, and neither skips fewer.
+  AmbiguousGaps(Vec<usize>, Vec<usize>),
+  // No stream found in an interleaved sequence claims the values at these indices.
+  Unclaimed(Vec<usize>),
+  // The solved state doesn't return what was observed at these indices, see `Verification`.
+  Unverified(Vec<usize>),
+  // No order of an unordered set of values, spread over up to this many calls, fits one state.
+  NoOrderFits(usize),
+  // V8 before 4.9 seeds MWC1616 from its own generator, not from `--random-seed` directly.
+  Unseedable,
+  // V8 takes a `--random-seed` of 0 as no seed at all, and seeds from entropy instead.
+  ZeroSeed,
+  // A seeded run has no values before its first call, so there's nothing to step back to.
+  BeforeSeed,
   MissingModel,
   EvalFailed(&'static str),
   ConvertFailed(&'static str),
@@ -18,6 +44,55 @@ impl Display for InitError {
     #[allow(clippy::implicit_return)]
     match self {
       Unsat => write!(f, "Solver returned UNSAT"),
+      NotEnoughObservations => write!(f, "Not enough observations to pin down the state"),
+      Ambiguous => write!(
+        f,
+        "More than one state fits the observations, add more of them"
+      ),
+      Inconsistent { indices, culprit } => {
+        let verb = if indices.len() == 1 { "is" } else { "are" };
+        write!(
+          f,
+          "The {} {verb} inconsistent with this generator",
+          listed("observation at index", "observations at indices", indices)
+        )?;
+        match culprit {
+          Some(culprit) => write!(f, ", dropping index {culprit} gives a unique solution"),
+          None => write!(f, ", and no single one can be dropped to fix it"),
+        }
+      }
+      UnsupportedObservation(reason) => write!(f, "Unsupported observation: {reason}"),
+      NoGapFits(max_gap) => write!(
+        f,
+        "No number of skipped calls, up to {max_gap}, fits between the fragments"
+      ),
+      AmbiguousGaps(gaps, others) => write!(
+        f,
+        "Skipping {gaps:?} or {others:?} calls between the fragments fits just as well, add more values"
+      ),
+      Unverified(indices) => write!(
+        f,
+        "The solved state doesn't return the {} observed, so the generator isn't the one modelled",
+        listed("value at index", "values at indices", indices)
+      ),
+      NoOrderFits(spread) => write!(
+        f,
+        "No order of the values, spread over up to {spread} calls, fits one state"
+      ),
+      Unclaimed(indices) => write!(
+        f,
+        "The {} fit none of the streams found, try more streams or a longer sequence",
+        listed("value at index", "values at indices", indices)
+      ),
+      Unseedable => write!(
+        f,
+        "V8 before 4.9 (Node.js 5 and older) can't be predicted from its seed"
+      ),
+      ZeroSeed => write!(
+        f,
+        "V8 ignores a seed of 0 and seeds from entropy, so the run can't be predicted"
+      ),
+      BeforeSeed => write!(f, "A seeded run has no values before its first call"),
       MissingModel => write!(f, "Failed to get model from solver"),
       EvalFailed(field) => write!(f, "Failed to evaluate {field}"),
       ConvertFailed(field) => write!(f, "Failed to convert {field} to u64"),
@@ -27,6 +102,91 @@ impl Display for InitError {
 
 impl Error for InitError {}
 
+// e.g. "value 3", "values 1, 3 and 4", or "no values".
+pub fn listed(singular: &str, plural: &str, indices: &[usize]) -> String {
+  let mut names: Vec<String> = indices
+    .iter()
+    .map(|index| {
+      return index.to_string();
+    })
+    .collect();
+  return match names.pop() {
+    None => format!("no {plural}"),
+    Some(last) if names.is_empty() => format!("{singular} {last}"),
+    Some(last) => format!("{plural} {} and {last}", names.join(", ")),
+  };
+}
+
+#[derive(Debug)]
+pub enum VersionError {
+  Malformed(String),
+  MissingField(&'static str),
+  NeedsSerde,
+}
+
+impl Display for VersionError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    use VersionError::*;
+    #[allow(clippy::implicit_return)]
+    match self {
+      Malformed(version) => write!(f, "Unable to parse version '{version}'"),
+      MissingField(field) => write!(f, "Versions are missing both 'v8' and '{field}'"),
+      NeedsSerde => write!(f, "Reading 'process.versions' needs the 'serde' feature"),
+    }
+  }
+}
+
+impl Error for VersionError {}
+
+#[derive(Debug)]
+pub enum StateError {
+  // Exported by a release of this crate that lays the state out differently.
+  Format(u32),
+  // Exported by a predictor for an engine the one picking it up doesn't predict.
+  WrongEngine(Engine),
+}
+
+impl Display for StateError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    use StateError::*;
+    #[allow(clippy::implicit_return)]
+    match self {
+      Format(format) => write!(
+        f,
+        "State format {format} isn't supported, this release reads format {}",
+        PredictorState::FORMAT
+      ),
+      WrongEngine(engine) => write!(f, "The state is from {engine}, another engine"),
+    }
+  }
+}
+
+impl Error for StateError {}
+
+// A `Predictor` method the predictor doesn't implement, named in the error.
+#[derive(Debug)]
+pub struct UnsupportedError(pub &'static str);
+
+impl Display for UnsupportedError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    return write!(f, "This predictor doesn't support '{}'", self.0);
+  }
+}
+
+impl Error for UnsupportedError {}
+
+// `skip` or `predict_at` asked for a call further from the predictor than a `u64` counts.
+#[derive(Debug)]
+pub struct JumpOverflowError;
+
+impl Display for JumpOverflowError {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    return write!(f, "Can't jump that far, the call index overflows a u64");
+  }
+}
+
+impl Error for JumpOverflowError {}
+
 #[derive(Debug)]
 pub struct PredictionLimitError;
 
@@ -1,130 +1,11 @@
-use crate::{Predictor, errors::InitError};
-use std::error::Error;
-use z3::{self, Config, Context, SatResult, Solver, ast::*};
+use crate::additive_engine::{AdditiveEngine, additive_predictor};
 
+#[derive(Clone)]
 pub struct FirefoxPredictor {
-  sequence: Vec<f64>,
-  is_solved: bool,
-  conc_state_0: u64,
-  conc_state_1: u64,
+  engine: AdditiveEngine,
 }
 
-impl Predictor for FirefoxPredictor {
-  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
-    self.solve_symbolic_state()?; // if solving fails, error is returned early
-    let v = self.xor_shift_128_plus_concrete();
-    return Ok(self.to_double(v));
-  }
-}
-
-impl FirefoxPredictor {
-  const SS_0_STR: &str = "sym_state_0";
-  const SS_1_STR: &str = "sym_state_1";
-
-  pub fn new(seq: Vec<f64>) -> Self {
-    return FirefoxPredictor {
-      sequence: seq,
-      is_solved: false,
-      conc_state_0: 0,
-      conc_state_1: 0,
-    };
-  }
-
-  #[allow(dead_code)]
-  pub fn sequence(&self) -> &[f64] {
-    return &self.sequence;
-  }
-
-  // So consumers don't have to import the Predictor trait as well as the struct.
-  pub fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
-    return <Self as Predictor>::predict_next(self);
-  }
-
-  fn xor_shift_128_plus_concrete(&mut self) -> u64 {
-    let mut s1 = self.conc_state_0;
-    let s0 = self.conc_state_1;
-    self.conc_state_0 = s0;
-    s1 = s1 ^ s1 << 23;
-    self.conc_state_1 = s1 ^ s0 ^ (s1 >> 17) ^ (s0 >> 26);
-    return self.conc_state_1.wrapping_add(s0);
-  }
-
-  fn to_double(&self, value: u64) -> f64 {
-    return ((value & 0x1FFFFFFFFFFFFF) as f64) / ((1u64 << 53) as f64);
-  }
-
-  fn solve_symbolic_state(&mut self) -> Result<(), InitError> {
-    if self.is_solved {
-      return Ok(());
-    }
-
-    let config = Config::new();
-    let context = Context::new(&config);
-    let solver = Solver::new(&context);
-
-    let mut sym_state_0 = BV::new_const(&context, Self::SS_0_STR, 64);
-    let mut sym_state_1 = BV::new_const(&context, Self::SS_1_STR, 64);
-
-    for &observed in &self.sequence {
-      Self::xor_shift_128_plus_symbolic(&context, &mut sym_state_0, &mut sym_state_1);
-      Self::constrain_mantissa(observed, &context, &solver, &sym_state_0, &sym_state_1);
-    }
-
-    if solver.check() != SatResult::Sat {
-      return Err(InitError::Unsat);
-    }
-
-    let model = solver.get_model().ok_or(InitError::MissingModel)?;
-
-    self.conc_state_0 = model
-      .eval(&sym_state_0, true)
-      .ok_or(InitError::EvalFailed(Self::SS_0_STR))?
-      .as_u64()
-      .ok_or(InitError::ConvertFailed(Self::SS_0_STR))?;
-
-    self.conc_state_1 = model
-      .eval(&sym_state_1, true)
-      .ok_or(InitError::EvalFailed(Self::SS_1_STR))?
-      .as_u64()
-      .ok_or(InitError::ConvertFailed(Self::SS_1_STR))?;
-
-    self.is_solved = true;
-    return Ok(());
-  }
-
-  // Static 'helper' method
-  fn xor_shift_128_plus_symbolic<'a>(
-    context: &'a Context,
-    state_0: &mut BV<'a>,
-    state_1: &mut BV<'a>,
-  ) {
-    let state_0_shifted_left = state_0.bvshl(&BV::from_u64(context, 23, 64));
-    let mut s1 = &*state_0 ^ state_0_shifted_left;
-    let s1_shifted_right = s1.bvlshr(&BV::from_u64(context, 17, 64));
-
-    s1 ^= s1_shifted_right;
-    s1 ^= state_1.clone();
-    s1 ^= state_1.bvlshr(&BV::from_u64(context, 26, 64));
-    std::mem::swap(state_0, state_1);
-    *state_1 = s1;
-  }
-
-  // Static 'helper' method
-  fn constrain_mantissa(
-    value: f64,
-    context: &Context,
-    solver: &Solver,
-    state_0: &BV,
-    state_1: &BV,
-  ) {
-    let sum = state_0.bvadd(state_1);
-    let symbolic_mask = BV::from_u64(context, 0x1FFFFFFFFFFFFF, 64);
-    let masked = sum.bvand(&symbolic_mask);
-    let mantissa = (value * (1u64 << 53) as f64) as u64;
-    let constraint = BV::from_u64(context, mantissa, 64)._eq(&masked).simplify();
-    solver.assert(&constraint);
-  }
-}
+additive_predictor!(FirefoxPredictor);
 
 #[cfg(test)]
 mod tests {
@@ -667,4 +548,475 @@ mod tests {
     assert_eq!(predictions, pool);
     return Ok(());
   }
+
+  #[test]
+  fn recovers_values_before_sequence() -> Result<(), Box<dyn Error>> {
+    let mut ffp = crate::FirefoxPredictor::new(vec![
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+    ]);
+
+    assert_eq!(ffp.predict_previous()?, 0.23093540482617203);
+    assert_eq!(
+      ffp.history(4)?,
+      vec![
+        0.5541046114391099,
+        0.21640895758393563,
+        0.7795614489825657,
+        0.45436917267245447,
+      ]
+    );
+    return Ok(());
+  }
+
+  #[test]
+  fn solves_around_calls_it_did_not_see() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Observation, SolverBackend};
+
+    let observations = Observation::sparse([
+      (0, 0.5865531271930553.into()),
+      (1, 0.5541046114391099.into()),
+      (2, 0.21640895758393563.into()),
+      (3, 0.7795614489825657.into()),
+      (6, 0.38347603573221434.into()),
+      (9, 0.8339269908305158.into()),
+      (10, 0.452233580000003.into()),
+    ]);
+
+    let mut ffp = FirefoxPredictor::from_observations(observations, SolverBackend::Native);
+    assert_eq!(ffp.predict_next()?, 0.9901079314416401);
+    assert_eq!(ffp.predict_next()?, 0.32987341924464075);
+
+    let verification = ffp.verify_observations()?;
+    assert!(verification.is_verified());
+    assert_eq!(
+      verification.replayed[4..7],
+      [
+        0.45436917267245447,
+        0.23093540482617203,
+        0.38347603573221434
+      ]
+    );
+    return Ok(());
+  }
+
+  #[test]
+  fn names_the_value_that_does_not_fit() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, errors::InitError};
+
+    // The last value is from another tab.
+    let sequence = vec![
+      0.5865531271930553,
+      0.5541046114391099,
+      0.21640895758393563,
+      0.7795614489825657,
+      0.45436917267245447,
+      0.3819660112501051,
+    ];
+
+    let mut ffp = FirefoxPredictor::new(sequence);
+    let error = ffp
+      .predict_next()
+      .err()
+      .ok_or("solved a sequence that doesn't fit")?;
+    match error.downcast_ref::<InitError>() {
+      Some(InitError::Inconsistent { indices, culprit }) => {
+        assert!(indices.contains(&5));
+        assert_eq!(*culprit, Some(5));
+      }
+      _ => return Err(error),
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn robust_mode_leaves_out_values_from_elsewhere() -> Result<(), Box<dyn Error>> {
+    use crate::FirefoxPredictor;
+
+    let mut sequence = vec![
+      0.5865531271930553,
+      0.5541046114391099,
+      0.21640895758393563,
+      0.7795614489825657,
+      0.45436917267245447,
+      0.23093540482617203,
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+      0.32987341924464075,
+    ];
+    // Pasted in from another tab.
+    sequence[9] = 0.3819660112501051;
+
+    let mut ffp = FirefoxPredictor::new(sequence);
+    ffp.set_robust(true);
+    assert_eq!(ffp.predict_next()?, 0.8988042405156045);
+    assert_eq!(ffp.rejected_indices()?, vec![9]);
+    return Ok(());
+  }
+
+  #[test]
+  fn jumps_ahead_without_stepping() -> Result<(), Box<dyn Error>> {
+    use crate::FirefoxPredictor;
+
+    let mut ffp = FirefoxPredictor::new(vec![
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+    ]);
+    assert_eq!(ffp.predict_at(0)?, 0.32987341924464075);
+
+    let mut stepped = ffp.fork();
+    let values: Vec<f64> = (0..1000)
+      .map(|_| {
+        return stepped.predict_next();
+      })
+      .collect::<Result<_, _>>()?;
+    assert_eq!(ffp.predict_at(999)?, values[999]);
+
+    ffp.skip(500)?;
+    assert_eq!(ffp.predict_next()?, values[500]);
+    assert!(ffp.predict_at(u64::MAX).is_err());
+    return Ok(());
+  }
+
+  #[test]
+  fn locates_values_either_way() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Location};
+
+    let sequence = vec![
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+    ];
+    let mut ffp = FirefoxPredictor::new(sequence.clone());
+    let value = ffp.predict_at(700)?;
+    assert_eq!(ffp.find_index(value, 1000)?, Some(Location::new(700)));
+    assert_eq!(ffp.find_index(sequence[5], 1000)?, Some(Location::new(-1)));
+    assert_eq!(ffp.find_index(sequence[0], 1000)?, Some(Location::new(-6)));
+    assert_eq!(ffp.find_index(value, 600)?, None);
+    return Ok(());
+  }
+
+  #[test]
+  fn links_sequences_of_one_stream() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Linkage, Location};
+
+    let seq_a = vec![
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+    ];
+    let mut ffp = FirefoxPredictor::new(seq_a.clone());
+    let later: Vec<f64> = (100..103)
+      .map(|index| {
+        return ffp.predict_at(index);
+      })
+      .collect::<Result<_, _>>()?;
+    // 50 values before `seq_a`, so the first of them is 56 calls before the one after it.
+    let earlier = ffp.history(50)?[10..13].to_vec();
+    let overlapping = vec![seq_a[4], seq_a[5], ffp.predict_at(0)?];
+
+    let link = |seq_b: Vec<f64>| {
+      return FirefoxPredictor::link(seq_a.clone(), seq_b, 1000);
+    };
+    assert_eq!(link(later)?, Linkage::Linked(vec![Location::new(100)]));
+    assert_eq!(link(earlier)?, Linkage::Linked(vec![Location::new(-46)]));
+    assert_eq!(link(overlapping)?, Linkage::Linked(vec![Location::new(-2)]));
+    assert_eq!(link(vec![0.1, 0.2])?, Linkage::Unlinked);
+    return Ok(());
+  }
+
+  #[test]
+  fn untangles_streams_of_two_contexts() -> Result<(), Box<dyn Error>> {
+    use crate::FirefoxPredictor;
+
+    let first = [
+      0.5865531271930553,
+      0.5541046114391099,
+      0.21640895758393563,
+      0.7795614489825657,
+      0.45436917267245447,
+      0.23093540482617203,
+      0.38347603573221434,
+      0.5711709968714335,
+    ];
+    // Far enough along in one generator to stand in for another context.
+    let mut ffp = FirefoxPredictor::new(first[..4].to_vec());
+    let second: Vec<f64> = (1000..1007)
+      .map(|index| {
+        return ffp.predict_at(index);
+      })
+      .collect::<Result<_, _>>()?;
+
+    let assignment = [0, 0, 0, 1, 0, 1, 1, 0, 1, 0, 1, 1, 0];
+    let (mut firsts, mut seconds) = (first.iter(), second.iter());
+    let sequence = assignment
+      .iter()
+      .map(|&stream| {
+        let values = if stream == 0 {
+          &mut firsts
+        } else {
+          &mut seconds
+        };
+        return values.next().copied().ok_or("ran out of values");
+      })
+      .collect::<Result<Vec<f64>, _>>()?;
+
+    let mut untangled = FirefoxPredictor::untangle(sequence, 2)?;
+    assert_eq!(untangled.assignment, assignment);
+    assert_eq!(untangled.streams[0].predict_next()?, first[7]);
+    assert_eq!(untangled.streams[1].predict_next()?, second[6]);
+    return Ok(());
+  }
+
+  #[test]
+  fn orders_values_collected_out_of_order() -> Result<(), Box<dyn Error>> {
+    use crate::FirefoxPredictor;
+
+    // Responses to parallel requests, and one of the calls went elsewhere.
+    let bag = vec![
+      0.5865531271930553,
+      0.5541046114391099,
+      0.7795614489825657,
+      0.21640895758393563,
+      0.38347603573221434,
+      0.45436917267245447,
+    ];
+    let mut unordered = FirefoxPredictor::from_unordered(bag, Some(7))?;
+
+    assert_eq!(unordered.positions, vec![0, 1, 3, 2, 6, 4]);
+    assert_eq!(unordered.order, vec![0, 1, 3, 2, 5, 4]);
+    assert_eq!(unordered.predictor.predict_next()?, 0.5711709968714335);
+    assert_eq!(unordered.predictor.predict_next()?, 0.30456387778967864);
+    return Ok(());
+  }
+
+  #[test]
+  fn picks_up_from_a_saved_state() -> Result<(), Box<dyn Error>> {
+    use crate::{Engine, FirefoxPredictor, NodePredictor, SafariPredictor, errors::StateError};
+
+    let mut ffp = FirefoxPredictor::new(vec![
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+    ]);
+    assert_eq!(ffp.predict_next()?, 0.32987341924464075);
+
+    let state = ffp.state()?;
+    let mut fork = ffp.fork();
+    let mut restored = FirefoxPredictor::from_state(state)?;
+    let expected = ffp.predict_next()?;
+    assert_eq!(fork.predict_next()?, expected);
+    assert_eq!(restored.predict_next()?, expected);
+    assert_eq!(restored.predict_previous()?, 0.23093540482617203);
+
+    // Safari runs the same engine, Node doesn't.
+    assert_eq!(
+      SafariPredictor::from_state(state)?.predict_next()?,
+      expected
+    );
+    assert!(matches!(
+      NodePredictor::from_state(state),
+      Err(StateError::WrongEngine(Engine::FirefoxSafari))
+    ));
+    return Ok(());
+  }
+
+  #[cfg(feature = "z3")]
+  #[test]
+  fn native_and_z3_solvers_agree() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, SolverBackend};
+
+    let sequence = vec![
+      0.983788222968869,
+      0.6210323993153665,
+      0.37646090421893474,
+      0.13923801694587312,
+    ];
+
+    let mut native = FirefoxPredictor::with_solver(sequence.clone(), SolverBackend::Native);
+    let mut z3 = FirefoxPredictor::with_solver(sequence, SolverBackend::Z3);
+
+    for _ in 0..25 {
+      assert_eq!(native.predict_next()?, z3.predict_next()?);
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn checks_dice_rolls_against_exact_values() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Observation, SolverBackend, errors::InitError};
+
+    let values: [f64; 13] = [
+      0.5865531271930553,
+      0.5541046114391099,
+      0.21640895758393563,
+      0.7795614489825657,
+      0.45436917267245447,
+      0.23093540482617203,
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+      0.32987341924464075,
+    ];
+    let roll = |value: &f64| {
+      return Observation::integer((value * 6.0).floor() as i64, 6);
+    };
+    // The exact values are solved with, the rolls of a six sided die after them only rule states out.
+    let mut observations: Vec<Observation> =
+      values[..6].iter().copied().map(Observation::from).collect();
+    observations.extend(
+      values[6..10]
+        .iter()
+        .map(roll)
+        .collect::<Result<Vec<_>, _>>()?,
+    );
+
+    let mut ffp = FirefoxPredictor::from_observations(observations.clone(), SolverBackend::Native);
+    for expected in &values[10..] {
+      assert_eq!(ffp.predict_next()?, *expected);
+    }
+
+    // A roll the state didn't make.
+    observations[8] = Observation::integer(((values[8] * 6.0).floor() as i64 + 1) % 6, 6)?;
+    let mut ffp = FirefoxPredictor::from_observations(observations, SolverBackend::Native);
+    ffp.engine.set_diagnose_unsat(false);
+    let error = ffp
+      .predict_next()
+      .err()
+      .ok_or("solved a roll that doesn't fit")?;
+    assert!(matches!(
+      error.downcast_ref::<InitError>(),
+      Some(InitError::Unsat)
+    ));
+    return Ok(());
+  }
+
+  #[test]
+  fn solves_from_dice_rolls() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Observation, SolverBackend, errors::InitError};
+
+    let mut source = FirefoxPredictor::new(vec![
+      0.983788222968869,
+      0.6210323993153665,
+      0.37646090421893474,
+      0.13923801694587312,
+    ]);
+    let values = (0..40)
+      .map(|_| {
+        return source.predict_next();
+      })
+      .collect::<Result<Vec<f64>, _>>()?;
+    let rolls = |sides: u64, count: usize| {
+      return values[..count]
+        .iter()
+        .map(|value| {
+          return Observation::integer((value * sides as f64).floor() as i64, sides);
+        })
+        .collect::<Result<Vec<_>, _>>();
+    };
+
+    // Each roll of a 1024 sided die gives away 10 bits, a couple dozen are plenty.
+    let mut ffp = FirefoxPredictor::from_observations(rolls(1024, 24)?, SolverBackend::Native);
+    for expected in &values[24..] {
+      assert_eq!(ffp.predict_next()?, *expected);
+    }
+
+    // A six sided die gives away too little for the native solver, however many rolls there are.
+    let mut ffp = FirefoxPredictor::from_observations(rolls(6, 40)?, SolverBackend::Native);
+    let error = ffp
+      .predict_next()
+      .err()
+      .ok_or("solved from six sided dice")?;
+    assert!(matches!(
+      error.downcast_ref::<InitError>(),
+      Some(InitError::UnsupportedObservation(_))
+    ));
+    return Ok(());
+  }
+
+  #[cfg(feature = "z3")]
+  #[test]
+  fn native_and_z3_solve_from_integers() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Observation, SolverBackend};
+
+    let values: [f64; 13] = [
+      0.5865531271930553,
+      0.5541046114391099,
+      0.21640895758393563,
+      0.7795614489825657,
+      0.45436917267245447,
+      0.23093540482617203,
+      0.38347603573221434,
+      0.5711709968714335,
+      0.30456387778967864,
+      0.8339269908305158,
+      0.452233580000003,
+      0.9901079314416401,
+      0.32987341924464075,
+    ];
+    let integers: Vec<Observation> = values[..6]
+      .iter()
+      .map(|value| {
+        return Observation::integer((value * (1u64 << 40) as f64).floor() as i64, 1 << 40);
+      })
+      .collect::<Result<_, _>>()?;
+
+    let mut native = FirefoxPredictor::from_observations(integers.clone(), SolverBackend::Native);
+    let mut z3 = FirefoxPredictor::from_observations(integers, SolverBackend::Z3);
+    for expected in &values[6..] {
+      assert_eq!(native.predict_next()?, *expected);
+      assert_eq!(z3.predict_next()?, *expected);
+    }
+    return Ok(());
+  }
+
+  #[cfg(feature = "z3")]
+  #[test]
+  fn reports_ambiguity_when_too_short() -> Result<(), Box<dyn Error>> {
+    use crate::{FirefoxPredictor, Observation, SolverBackend, errors::InitError};
+
+    // Two doubles and a 10 bit integer leave a dozen bits of the state unknown.
+    let observations = vec![
+      Observation::Exact(0.5865531271930553),
+      Observation::Exact(0.5541046114391099),
+      Observation::integer((0.21640895758393563 * 1024.0f64).floor() as i64, 1024)?,
+    ];
+    let mut ffp = FirefoxPredictor::from_observations(observations.clone(), SolverBackend::Z3);
+    let error = ffp.predict_next().err();
+    assert!(matches!(
+      error.as_deref().and_then(|e| {
+        return e.downcast_ref::<InitError>();
+      }),
+      Some(InitError::Ambiguous)
+    ));
+    assert_eq!(ffp.candidate_states(3)?.len(), 3);
+
+    ffp.set_allow_ambiguous(true);
+    assert!(ffp.predict_next().is_ok());
+    return Ok(());
+  }
 }
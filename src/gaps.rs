@@ -0,0 +1,204 @@
+use crate::{Engine, Observation, Predictor, SolverBackend, errors::InitError};
+
+// How many calls we didn't see came before each fragment of consecutive observations, after the
+// first, eg. the calls a console makes between the batches we copy out of it. Every gap is at
+// most `max_gap`, and of the combinations that make the whole sequence fit `engine`, the one that
+// skips the fewest calls in total wins. When another skips as few, it's `InitError::AmbiguousGaps`,
+// and when the one that wins fits more than one state, `InitError::Ambiguous`.
+//
+// Fragments are joined one at a time, with every gap before the next one tried in turn. Only
+// while the fragments so far leave the state open does each gap take a solve. Once they pin it
+// down, the state is run forward to find where each of the fragments after them fits, with no more
+// solving. Combinations that already skip more calls than the best one found so far are dropped.
+pub fn find_gaps(
+  engine: Engine,
+  fragments: &[Vec<Observation>],
+  max_gap: usize,
+  solver: SolverBackend,
+) -> Result<Vec<usize>, InitError> {
+  let mut search = GapSearch {
+    engine,
+    fragments,
+    max_gap,
+    solver,
+    best: None,
+  };
+  search.join(&mut vec![]);
+
+  let (_, fits, is_ambiguous) = search.best.ok_or(InitError::NoGapFits(max_gap))?;
+  if fits.len() > 1 {
+    return Err(InitError::AmbiguousGaps(fits[0].clone(), fits[1].clone()));
+  }
+  if is_ambiguous {
+    return Err(InitError::Ambiguous);
+  }
+  return Ok(fits[0].clone());
+}
+
+struct GapSearch<'a> {
+  engine: Engine,
+  fragments: &'a [Vec<Observation>],
+  max_gap: usize,
+  solver: SolverBackend,
+  // The fewest calls skipped in total, every combination of gaps that skips that many, and whether
+  // any of them fit more than one state.
+  best: Option<(usize, Vec<Vec<usize>>, bool)>,
+}
+
+impl GapSearch<'_> {
+  // Solves the fragments up to the one after `gaps`, and tries every gap after them.
+  fn join(&mut self, gaps: &mut Vec<usize>) {
+    let num_joined = (gaps.len() + 1).min(self.fragments.len());
+    let is_complete = num_joined == self.fragments.len();
+
+    let observations = Observation::joined(&self.fragments[..num_joined], gaps);
+    match self.engine.solve(observations, self.solver, false) {
+      Ok(_) if is_complete => self.fits(gaps, false),
+      Ok(mut predictor) => self.run_forward(predictor.as_mut(), gaps, 0),
+      // Fits, but not well enough yet to rule out any gap after it.
+      Err(error) => match error.downcast_ref::<InitError>() {
+        Some(InitError::Ambiguous) if is_complete => self.fits(gaps, true),
+        Some(InitError::NotEnoughObservations | InitError::Ambiguous) => {
+          for gap in 0..=self.max_gap {
+            gaps.push(gap);
+            if self.is_in_bound(gaps) {
+              self.join(gaps);
+            }
+            gaps.pop();
+          }
+        }
+        _ => {}
+      },
+    }
+  }
+
+  // Tries every gap before the fragment after `gaps`, with `predictor` pinned down by the ones
+  // before it, and `call` calls already taken by the fragments since.
+  fn run_forward(&mut self, predictor: &mut dyn Predictor, gaps: &mut Vec<usize>, call: u64) {
+    let Some(fragment) = self.fragments.get(gaps.len() + 1) else {
+      self.fits(gaps, false);
+      return;
+    };
+    for gap in 0..=self.max_gap {
+      gaps.push(gap);
+      let start = call + gap as u64;
+      let is_match = (0..fragment.len()).all(|k| {
+        return predictor.predict_at(start + k as u64).is_ok_and(|value| {
+          return fragment[k].matches(value);
+        });
+      });
+      if is_match && self.is_in_bound(gaps) {
+        self.run_forward(predictor, gaps, start + fragment.len() as u64);
+      }
+      gaps.pop();
+    }
+  }
+
+  // Whether `gaps` skip no more calls than the best combination found so far.
+  fn is_in_bound(&self, gaps: &[usize]) -> bool {
+    return self.best.as_ref().is_none_or(|(fewest, _, _)| {
+      return gaps.iter().sum::<usize>() <= *fewest;
+    });
+  }
+
+  fn fits(&mut self, gaps: &[usize], is_ambiguous: bool) {
+    let skipped = gaps.iter().sum::<usize>();
+    match &mut self.best {
+      Some((fewest, fits, was_ambiguous)) if skipped == *fewest => {
+        fits.push(gaps.to_vec());
+        *was_ambiguous |= is_ambiguous;
+      }
+      Some((fewest, _, _)) if skipped > *fewest => {}
+      _ => self.best = Some((skipped, vec![gaps.to_vec()], is_ambiguous)),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::find_gaps;
+  use crate::{Engine, FirefoxPredictor, Observation, SolverBackend, V8Variant, errors::InitError};
+  use std::error::Error;
+
+  fn fragment(values: &[f64]) -> Vec<Observation> {
+    return values.iter().copied().map(Observation::from).collect();
+  }
+
+  #[test]
+  fn finds_calls_skipped_between_firefox_batches() -> Result<(), Box<dyn Error>> {
+    let fragments = [
+      fragment(&[
+        0.5865531271930553,
+        0.5541046114391099,
+        0.21640895758393563,
+        0.7795614489825657,
+        0.45436917267245447,
+        0.23093540482617203,
+      ]),
+      fragment(&[0.8339269908305158, 0.452233580000003]),
+      fragment(&[0.49552711891551626]),
+    ];
+
+    let gaps = find_gaps(Engine::FirefoxSafari, &fragments, 20, SolverBackend::Native)?;
+    assert_eq!(gaps, vec![3, 3]);
+
+    let observations = Observation::joined(&fragments, &gaps);
+    let mut ffp = FirefoxPredictor::from_observations(observations, SolverBackend::Native);
+    assert_eq!(ffp.predict_next()?, 0.15029415930638967);
+    return Ok(());
+  }
+
+  #[test]
+  fn reports_two_gap_combinations_that_fit() -> Result<(), Box<dyn Error>> {
+    // The 1 in 3 pick fits the call before the skipped one as well as the one after it.
+    let fragments = [
+      fragment(&[
+        0.5865531271930553,
+        0.5541046114391099,
+        0.21640895758393563,
+        0.7795614489825657,
+        0.45436917267245447,
+        0.23093540482617203,
+      ]),
+      vec![Observation::integer(1, 3)?],
+      fragment(&[0.30456387778967864]),
+    ];
+
+    match find_gaps(Engine::FirefoxSafari, &fragments, 20, SolverBackend::Native) {
+      Err(InitError::AmbiguousGaps(gaps, others)) => {
+        assert_eq!(gaps, vec![0, 1]);
+        assert_eq!(others, vec![1, 0]);
+      }
+      result => return Err(format!("expected two combinations, got {result:?}").into()),
+    }
+
+    return Ok(());
+  }
+
+  #[test]
+  fn finds_calls_skipped_between_node_batches() -> Result<(), Box<dyn Error>> {
+    let fragments = [
+      fragment(&[0.01800425609760259, 0.19267361208155598, 0.9892770985784053]),
+      fragment(&[0.8664993194151147]),
+    ];
+
+    let gaps = find_gaps(
+      Engine::V8(V8Variant::Mantissa53),
+      &fragments,
+      10,
+      SolverBackend::Native,
+    )?;
+    assert_eq!(gaps, vec![2]);
+
+    assert!(
+      find_gaps(
+        Engine::V8(V8Variant::Mantissa53),
+        &fragments,
+        1,
+        SolverBackend::Native
+      )
+      .is_err()
+    );
+    return Ok(());
+  }
+}
@@ -0,0 +1,274 @@
+use std::ops::{BitXor, BitXorAssign};
+
+// A 64 bit word where every bit is a linear combination (over GF(2)) of the
+// 128 unknown bits of a xorshift128+ state. Bit `i` of `state_0` is unknown `i`,
+// bit `i` of `state_1` is unknown `64 + i`.
+#[derive(Clone, Copy)]
+pub(crate) struct LinearWord([u128; 64]);
+
+impl LinearWord {
+  pub fn state_0() -> Self {
+    return LinearWord(std::array::from_fn(|i| {
+      return 1u128 << i;
+    }));
+  }
+
+  pub fn state_1() -> Self {
+    return LinearWord(std::array::from_fn(|i| {
+      return 1u128 << (64 + i);
+    }));
+  }
+
+  pub fn bit(&self, index: usize) -> u128 {
+    return self.0[index];
+  }
+
+  pub fn shl(&self, n: usize) -> Self {
+    return LinearWord(std::array::from_fn(|i| {
+      return if i >= n { self.0[i - n] } else { 0 };
+    }));
+  }
+
+  pub fn lshr(&self, n: usize) -> Self {
+    return LinearWord(std::array::from_fn(|i| {
+      return if i + n < 64 { self.0[i + n] } else { 0 };
+    }));
+  }
+
+  // Evaluates every bit of this word against a concrete assignment of the unknowns.
+  pub fn eval(&self, unknowns: u128) -> u64 {
+    let mut value = 0u64;
+    for (i, mask) in self.0.iter().enumerate() {
+      value |= (((mask & unknowns).count_ones() & 1) as u64) << i;
+    }
+    return value;
+  }
+}
+
+impl BitXor for LinearWord {
+  type Output = LinearWord;
+
+  fn bitxor(self, rhs: Self) -> Self::Output {
+    return LinearWord(std::array::from_fn(|i| {
+      return self.0[i] ^ rhs.0[i];
+    }));
+  }
+}
+
+impl BitXorAssign for LinearWord {
+  fn bitxor_assign(&mut self, rhs: Self) {
+    for (lhs, rhs) in self.0.iter_mut().zip(rhs.0) {
+      *lhs ^= rhs;
+    }
+  }
+}
+
+// Mirrors the symbolic z3 step, but on `LinearWord`s.
+pub(crate) fn xor_shift_128_plus_linear(state_0: &mut LinearWord, state_1: &mut LinearWord) {
+  let mut s1 = *state_0 ^ state_0.shl(23);
+  s1 ^= s1.lshr(17);
+  s1 ^= *state_1;
+  s1 ^= state_1.lshr(26);
+  std::mem::swap(state_0, state_1);
+  *state_1 = s1;
+}
+
+// Free variables the native solvers will enumerate, when observations leave some of the state
+// undetermined. Past this, we ask for more observations instead of guessing.
+pub(crate) const MAX_FREE_VARIABLES: usize = 16;
+
+// Every way of setting the `free` unknowns, with all the others left at 0.
+pub(crate) fn free_assignments(free: &[usize]) -> impl Iterator<Item = u128> + '_ {
+  return (0..(1u64 << free.len())).map(|assignment| {
+    let mut free_bits = 0u128;
+    for (j, &variable) in free.iter().enumerate() {
+      free_bits |= (((assignment >> j) & 1) as u128) << variable;
+    }
+    return free_bits;
+  });
+}
+
+// Incremental Gaussian elimination over the 128 unknowns of a xorshift128+ state.
+// Each row is stored at the index of its highest set bit.
+#[derive(Clone)]
+pub(crate) struct Gf2System {
+  rows: [Option<(u128, bool)>; 128],
+}
+
+impl Gf2System {
+  pub fn new() -> Self {
+    return Gf2System { rows: [None; 128] };
+  }
+
+  // Adds `parity(mask & unknowns) == value`. Returns false if the equation contradicts the system.
+  pub fn add_equation(&mut self, mut mask: u128, mut value: bool) -> bool {
+    while mask != 0 {
+      let pivot = 127 - mask.leading_zeros() as usize;
+      match self.rows[pivot] {
+        Some((row_mask, row_value)) => {
+          mask ^= row_mask;
+          value ^= row_value;
+        }
+        None => {
+          self.rows[pivot] = Some((mask, value));
+          return true;
+        }
+      }
+    }
+    // The equation reduced to `0 == value`.
+    return !value;
+  }
+
+  // Constrains every bit of `word` selected by `bits` to match `value`.
+  pub fn add_word(&mut self, word: &LinearWord, bits: std::ops::Range<usize>, value: u64) -> bool {
+    for i in bits {
+      if !self.add_equation(word.bit(i), (value >> i) & 1 == 1) {
+        return false;
+      }
+    }
+    return true;
+  }
+
+  // Unknowns no equation pins down.
+  pub fn free_variables(&self) -> Vec<usize> {
+    return (0..128)
+      .filter(|&i| {
+        return self.rows[i].is_none();
+      })
+      .collect();
+  }
+
+  // Back-substitution. Unknowns without a pivot (free variables) are taken from `free`.
+  pub fn solve_with(&self, free: u128) -> u128 {
+    let mut unknowns = 0u128;
+    for i in self.free_variables() {
+      unknowns |= free & (1u128 << i);
+    }
+    for (pivot, row) in self.rows.iter().enumerate() {
+      if let Some((mask, value)) = row {
+        let rest = mask & !(1u128 << pivot);
+        let bit = *value ^ ((rest & unknowns).count_ones() & 1 == 1);
+        unknowns |= (bit as u128) << pivot;
+      }
+    }
+    return unknowns;
+  }
+}
+
+// Gaussian elimination over a fixed sequence of equations whose right hand sides are not known
+// up front. Every equation is either a new pivot, or the sum of equations pushed before it.
+// Checking a set of right hand sides is then just a parity check per dependent equation.
+pub(crate) struct Elimination {
+  rows: Vec<Option<(u128, EquationSet)>>,
+  num_equations: usize,
+  capacity: usize,
+}
+
+pub(crate) enum Reduction {
+  Pivot,
+  // The (earlier) equations this equation is the sum of.
+  Dependent(EquationSet),
+}
+
+impl Elimination {
+  pub fn new(capacity: usize) -> Self {
+    return Elimination {
+      rows: vec![None; 128],
+      num_equations: 0,
+      capacity,
+    };
+  }
+
+  // Pushes the left hand side of the next equation, `parity(mask & unknowns)`.
+  pub fn push(&mut self, mut mask: u128) -> Reduction {
+    let mut sources = EquationSet::new(self.capacity);
+    sources.set(self.num_equations, true);
+    self.num_equations += 1;
+
+    while mask != 0 {
+      let pivot = 127 - mask.leading_zeros() as usize;
+      match &self.rows[pivot] {
+        Some((row_mask, row_sources)) => {
+          mask ^= row_mask;
+          sources.xor(row_sources);
+        }
+        None => {
+          self.rows[pivot] = Some((mask, sources));
+          return Reduction::Pivot;
+        }
+      }
+    }
+
+    // `sources` sums to zero, so this equation is the sum of the others in it.
+    sources.set(self.num_equations - 1, false);
+    return Reduction::Dependent(sources);
+  }
+
+  // Unknowns no equation pins down.
+  pub fn free_variables(&self) -> Vec<usize> {
+    return (0..128)
+      .filter(|&i| {
+        return self.rows[i].is_none();
+      })
+      .collect();
+  }
+
+  // Back-substitution using the right hand sides in `values`, with free variables taken from `free`.
+  pub fn solve_with(&self, values: &EquationSet, free: u128) -> u128 {
+    let mut unknowns = 0u128;
+    for (pivot, row) in self.rows.iter().enumerate() {
+      match row {
+        Some((mask, sources)) => {
+          let rest = mask & !(1u128 << pivot);
+          let bit = sources.parity(values) ^ ((rest & unknowns).count_ones() & 1 == 1);
+          unknowns |= (bit as u128) << pivot;
+        }
+        None => unknowns |= free & (1u128 << pivot),
+      }
+    }
+    return unknowns;
+  }
+}
+
+// A set of equation indices, which doubles as a vector of right hand sides.
+#[derive(Clone)]
+pub(crate) struct EquationSet(Vec<u64>);
+
+impl EquationSet {
+  pub fn new(capacity: usize) -> Self {
+    return EquationSet(vec![0; capacity.div_ceil(64)]);
+  }
+
+  pub fn set(&mut self, index: usize, value: bool) {
+    let bit = 1u64 << (index % 64);
+    if value {
+      self.0[index / 64] |= bit;
+    } else {
+      self.0[index / 64] &= !bit;
+    }
+  }
+
+  // How many equations there's room for.
+  pub fn capacity(&self) -> usize {
+    return self.0.len() * 64;
+  }
+
+  pub fn get(&self, index: usize) -> bool {
+    return (self.0[index / 64] >> (index % 64)) & 1 == 1;
+  }
+
+  pub fn xor(&mut self, other: &EquationSet) {
+    for (lhs, rhs) in self.0.iter_mut().zip(&other.0) {
+      *lhs ^= rhs;
+    }
+  }
+
+  // Parity of the members of `self` that are set in `values`.
+  pub fn parity(&self, values: &EquationSet) -> bool {
+    let mut parity = 0;
+    for (lhs, rhs) in self.0.iter().zip(&values.0) {
+      parity ^= (lhs & rhs).count_ones();
+    }
+    return parity & 1 == 1;
+  }
+}
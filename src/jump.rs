@@ -0,0 +1,81 @@
+use crate::v8_predictor::V8Predictor;
+use std::sync::OnceLock;
+
+// xorshift128+ is linear over GF(2), so stepping its state `n` times is one 128x128 bit matrix.
+// Squaring the matrix of a single step over and over gives the one for every power of two, and any
+// `n` is the product of those for the bits set in it. So a jump of any length takes at most 64
+// matrix-vector products, instead of `n` steps.
+
+// Each column is where one bit of the state ends up, `state_0` in the low half, `state_1` the high.
+type Matrix = [u128; 128];
+
+// The matrices of 2^0, 2^1, ... 2^63 steps, worked out the first time they're needed.
+fn powers() -> &'static [Matrix] {
+  static POWERS: OnceLock<Vec<Matrix>> = OnceLock::new();
+  return POWERS.get_or_init(|| {
+    let mut step = [0u128; 128];
+    for (bit, column) in step.iter_mut().enumerate() {
+      let (mut state_0, mut state_1) = split(1u128 << bit);
+      V8Predictor::xor_shift_128_plus_forward(&mut state_0, &mut state_1);
+      *column = join(state_0, state_1);
+    }
+    let mut powers = vec![step];
+    for _ in 1..64 {
+      let last = powers[powers.len() - 1];
+      let mut squared = [0u128; 128];
+      for (bit, column) in squared.iter_mut().enumerate() {
+        *column = apply(&last, last[bit]);
+      }
+      powers.push(squared);
+    }
+    return powers;
+  });
+}
+
+fn apply(matrix: &Matrix, state: u128) -> u128 {
+  let mut result = 0;
+  for (bit, column) in matrix.iter().enumerate() {
+    if (state >> bit) & 1 == 1 {
+      result ^= column;
+    }
+  }
+  return result;
+}
+
+fn split(state: u128) -> (u64, u64) {
+  return (state as u64, (state >> 64) as u64);
+}
+
+fn join(state_0: u64, state_1: u64) -> u128 {
+  return (state_1 as u128) << 64 | state_0 as u128;
+}
+
+// Steps `(state_0, state_1)` forward `steps` times, the way `xor_shift_128_plus_forward` does.
+pub(crate) fn xor_shift_128_plus_jump(state_0: &mut u64, state_1: &mut u64, steps: u64) {
+  let mut state = join(*state_0, *state_1);
+  for (power, matrix) in powers().iter().enumerate() {
+    if (steps >> power) & 1 == 1 {
+      state = apply(matrix, state);
+    }
+  }
+  (*state_0, *state_1) = split(state);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::xor_shift_128_plus_jump;
+  use crate::v8_predictor::{V8Predictor, test_values::TEST_STATE};
+
+  #[test]
+  fn jumps_where_stepping_goes() {
+    let (mut state_0, mut state_1) = TEST_STATE;
+    let (mut jumped_0, mut jumped_1) = (state_0, state_1);
+    for steps in [0, 1, 2, 63, 64, 1000] {
+      for _ in 0..steps {
+        V8Predictor::xor_shift_128_plus_forward(&mut state_0, &mut state_1);
+      }
+      xor_shift_128_plus_jump(&mut jumped_0, &mut jumped_1, steps);
+      assert_eq!((jumped_0, jumped_1), (state_0, state_1));
+    }
+  }
+}
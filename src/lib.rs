@@ -7,16 +7,48 @@ mod firefox_predictor;
 mod node_predictor;
 mod safari_predictor;
 
+mod additive_engine;
+mod additive_solver;
+mod chrome_major_version;
+mod detect;
+mod diagnosis;
+mod gaps;
+mod gf2_solver;
+mod jump;
+mod link;
+mod location;
+mod mwc1616_predictor;
 mod nodejs_major_version;
+mod observation;
 mod predictor;
+mod predictor_state;
+mod robust;
+mod solver_backend;
+mod unordered;
+mod untangle;
+mod v8_predictor;
+mod v8_version;
+mod verification;
 
 // Public exports
 
 pub mod errors;
 
+pub use chrome_major_version::ChromeMajorVersion;
 pub use chrome_predictor::ChromePredictor;
+pub use detect::{Detection, Engine, detect, detect_with_solver};
 pub use firefox_predictor::FirefoxPredictor;
+pub use gaps::find_gaps;
+pub use link::Linkage;
+pub use location::Location;
 pub use node_predictor::NodePredictor;
 pub use nodejs_major_version::NodeJsMajorVersion;
+pub use observation::Observation;
 pub use predictor::Predictor;
+pub use predictor_state::PredictorState;
 pub use safari_predictor::SafariPredictor;
+pub use solver_backend::SolverBackend;
+pub use unordered::Unordered;
+pub use untangle::Untangled;
+pub use v8_version::{Runtime, V8Variant, V8Version};
+pub use verification::Verification;
@@ -0,0 +1,75 @@
+use crate::{Location, Predictor, errors::InitError};
+use std::error::Error;
+
+// Whether a second sequence, B, came from the same generator as a first one, A, eg. the same tab
+// or session, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Linkage {
+  // Where B's first value is, counting calls from the one after A's last, so 0 means B carries on
+  // right after A, and a negative index that it started before A ended. More than one when the
+  // sequences only say they're in the same stream, not how far apart, see `link`.
+  Linked(Vec<Location>),
+  // B isn't within `max_distance` calls of A: it's from another context, or further away.
+  Unlinked,
+}
+
+// Looks for B's first value around A, then checks the rest of B follows it there. `build` solves A
+// with that many unknown calls in front of it, and `placements` says how many of those to try
+// once A is solved: the number of places it could have started at in V8's cache, if the sequence
+// didn't say, since that changes how far away everything outside A's cache is. Every distance B
+// fits at is kept.
+pub(crate) fn link<P: Predictor + Clone>(
+  seq_b: &[f64],
+  max_distance: u64,
+  build: impl Fn(usize) -> Result<P, Box<dyn Error>>,
+  placements: impl Fn(&mut P) -> Result<usize, Box<dyn Error>>,
+) -> Result<Linkage, Box<dyn Error>> {
+  let Some(&first) = seq_b.first() else {
+    return Err(Box::new(InitError::NotEnoughObservations));
+  };
+  let mut solved = build(0)?;
+  let mut locations: Vec<Location> = vec![];
+  for lead_in in 0..placements(&mut solved)? {
+    let mut a = if lead_in == 0 {
+      solved.clone()
+    } else {
+      build(lead_in)?
+    };
+    let Some(location) = a.find_index(first, max_distance)? else {
+      continue;
+    };
+    let is_new = locations.iter().all(|found| {
+      return found.index != location.index;
+    });
+    if is_new && values_from(&mut a, location.index, seq_b.len())? == seq_b {
+      locations.push(location);
+    }
+  }
+  if locations.is_empty() {
+    return Ok(Linkage::Unlinked);
+  }
+  return Ok(Linkage::Linked(locations));
+}
+
+// `count` values from `first` on, counting calls from the one after the sequence `predictor` was
+// solved from. It hasn't predicted anything yet, so that's also where it is.
+fn values_from<P: Predictor + Clone>(
+  predictor: &mut P,
+  first: i64,
+  count: usize,
+) -> Result<Vec<f64>, Box<dyn Error>> {
+  let mut values = vec![];
+  if first < 0 {
+    // What came before, then the sequence, up to the call before where the predictor is.
+    let mut behind = predictor.clone();
+    let sequence = behind.verify_observations()?.replayed;
+    let mut earlier = behind.history((-first - sequence.len() as i64).max(0) as usize)?;
+    earlier.extend(sequence);
+    let start = (earlier.len() as i64 + first) as usize;
+    values.extend(earlier.iter().skip(start).take(count));
+  }
+  for index in first.max(0)..(first + count as i64) {
+    values.push(predictor.predict_at(index as u64)?);
+  }
+  return Ok(values);
+}
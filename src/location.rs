@@ -0,0 +1,97 @@
+// Where a value turns up, counting calls from where the predictor is: 0 is the value
+// `predict_next` returns next, 1 the one after, and -1 the value it returned last (or the last of
+// the sequence). So the difference between two of them is how many calls came in between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+  pub index: i64,
+  // V8 with a cache only: which cache the value is handed out from, 0 being the one `pool_index`
+  // counts, 1 the next refill and -1 the cache before, and how many of that cache came before it.
+  pub pool: Option<i64>,
+  pub slot: Option<u8>,
+}
+
+impl Location {
+  pub(crate) fn new(index: i64) -> Self {
+    return Location {
+      index,
+      pool: None,
+      slot: None,
+    };
+  }
+
+  // `reads` is how many values of the current cache were handed out.
+  pub(crate) fn in_cache(index: i64, reads: u8, cache_size: usize) -> Self {
+    let read = reads as i64 + index;
+    return Location {
+      index,
+      pool: Some(read.div_euclid(cache_size as i64)),
+      slot: Some(read.rem_euclid(cache_size as i64) as u8),
+    };
+  }
+}
+
+// The index `value` turns up at, looking up to `max_distance` calls after and before now. `next`
+// and `previous` return the values that way, one call further away each time. Plain steps, no
+// solving, so going a million calls each way takes well under a second. At the same distance,
+// after wins.
+pub(crate) fn nearest(
+  value: f64,
+  max_distance: u64,
+  mut next: impl FnMut() -> f64,
+  mut previous: impl FnMut() -> f64,
+) -> Option<i64> {
+  if next() == value {
+    return Some(0);
+  }
+  for distance in 1..=max_distance as i64 {
+    if next() == value {
+      return Some(distance);
+    }
+    if previous() == value {
+      return Some(-distance);
+    }
+  }
+  return None;
+}
+
+#[cfg(test)]
+mod tests {
+  use super::{Location, nearest};
+
+  #[test]
+  fn counts_reads_into_other_caches() {
+    assert_eq!(
+      Location::in_cache(-5, 3, 64),
+      Location {
+        index: -5,
+        pool: Some(-1),
+        slot: Some(62),
+      }
+    );
+    assert_eq!(Location::in_cache(61, 3, 64).pool, Some(1));
+  }
+
+  #[test]
+  fn finds_the_closest_value_first() {
+    let find = |value: f64, max_distance: u64| {
+      let mut after = [0.1, 0.2, 0.3, 0.4].into_iter();
+      let mut before = [0.6, 0.3, 0.7].into_iter();
+      return nearest(
+        value,
+        max_distance,
+        || {
+          return after.next().unwrap_or(1.0);
+        },
+        || {
+          return before.next().unwrap_or(1.0);
+        },
+      );
+    };
+    assert_eq!(find(0.1, 3), Some(0));
+    assert_eq!(find(0.6, 3), Some(-1));
+    // 0.3 is two calls away both ways.
+    assert_eq!(find(0.3, 3), Some(2));
+    assert_eq!(find(0.7, 2), None);
+    assert_eq!(find(0.7, 3), Some(-3));
+  }
+}
@@ -0,0 +1,948 @@
+#[cfg(feature = "z3")]
+use crate::diagnosis::unsat_core;
+use crate::{
+  Engine, Location, Observation, Predictor, PredictorState, SolverBackend, V8Variant,
+  diagnosis::diagnose,
+  errors::{InitError, JumpOverflowError},
+  location::nearest,
+  observation::exact_values,
+  robust::outliers,
+  unordered::order,
+  untangle::untangle,
+  verification::Verification,
+};
+use std::error::Error;
+#[cfg(feature = "z3")]
+use z3::{Config, Context, SatResult, Solver, ast::*};
+
+// Multipliers of the (high, low) halves of the output, in the order V8 shipped them. Newest first,
+// which is what a sequence too short to tell them apart is assumed to come from.
+// - V8 4.x `math.js`: 18030 and 36969.
+// - V8 3.x `math.js`: 18273 and 36969.
+// - V8 3.x C++ `random_base`: 36969 and 18273.
+const MULTIPLIERS: [(u32, u32); 3] = [(18030, 36969), (18273, 36969), (36969, 18273)];
+
+// Multipliers, and the state behind the first value of the sequence, that fit the observations.
+type Candidate = ((u32, u32), u32, u32);
+
+// MWC1616, used by V8 before 4.9. Two 32 bit multiply-with-carry generators, each contributing 16
+// bits to every output. There is no cache, every call to `Math.random()` steps the generator once.
+//
+// Each output holds the low 16 bits of both states, and the high 16 bits (the carry) of a state is
+// never more than the multiplier. So a state can be searched for, one half at a time.
+#[derive(Clone)]
+pub(crate) struct Mwc1616Predictor {
+  // The exact values observed, `observations` also has any that were only partly observed.
+  sequence: Vec<f64>,
+  observations: Vec<Observation>,
+  is_solved: bool,
+  solver: SolverBackend,
+  multipliers: (u32, u32),
+  // State behind the last value handed out.
+  conc_state_0: u32,
+  conc_state_1: u32,
+  // State behind the earliest value returned so far, used by `predict_previous`.
+  prev_state_0: u32,
+  prev_state_1: u32,
+  // Predict from the first state found, even if others fit the observations too.
+  pub(crate) allows_ambiguity: bool,
+  // Work out which observations are to blame when no state fits, see `InitError::Inconsistent`.
+  pub(crate) diagnoses_unsat: bool,
+  // Leave out observations that don't fit the state most of the others do.
+  pub(crate) is_robust: bool,
+  rejected: Option<Vec<usize>>,
+  // What the solved state returns for each observed call, see `Verification`.
+  replayed: Vec<f64>,
+}
+
+impl Predictor for Mwc1616Predictor {
+  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?; // if solving fails, error is returned early
+    let (multiplier_0, multiplier_1) = self.multipliers;
+    self.conc_state_0 = Self::mwc_forward(self.conc_state_0, multiplier_0);
+    self.conc_state_1 = Self::mwc_forward(self.conc_state_1, multiplier_1);
+    return Ok(Self::to_double(self.conc_state_0, self.conc_state_1));
+  }
+
+  fn predict_previous(&mut self) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let (multiplier_0, multiplier_1) = self.multipliers;
+    self.prev_state_0 = Self::mwc_reverse(self.prev_state_0, multiplier_0);
+    self.prev_state_1 = Self::mwc_reverse(self.prev_state_1, multiplier_1);
+    return Ok(Self::to_double(self.prev_state_0, self.prev_state_1));
+  }
+
+  fn skip(&mut self, n: u64) -> Result<(), Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let (multiplier_0, multiplier_1) = self.multipliers;
+    self.conc_state_0 = Self::mwc_jump(self.conc_state_0, multiplier_0, n);
+    self.conc_state_1 = Self::mwc_jump(self.conc_state_1, multiplier_1, n);
+    return Ok(());
+  }
+
+  fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let steps = index.checked_add(1).ok_or(JumpOverflowError)?;
+    let (multiplier_0, multiplier_1) = self.multipliers;
+    return Ok(Self::to_double(
+      Self::mwc_jump(self.conc_state_0, multiplier_0, steps),
+      Self::mwc_jump(self.conc_state_1, multiplier_1, steps),
+    ));
+  }
+
+  fn find_index(
+    &mut self,
+    value: f64,
+    max_distance: u64,
+  ) -> Result<Option<Location>, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let (multiplier_0, multiplier_1) = self.multipliers;
+    let (mut next_0, mut next_1) = (self.conc_state_0, self.conc_state_1);
+    // The last value returned came from the state we're at.
+    let (mut prev_0, mut prev_1) = (next_0, next_1);
+    let index = nearest(
+      value,
+      max_distance,
+      || {
+        next_0 = Self::mwc_forward(next_0, multiplier_0);
+        next_1 = Self::mwc_forward(next_1, multiplier_1);
+        return Self::to_double(next_0, next_1);
+      },
+      || {
+        let v = Self::to_double(prev_0, prev_1);
+        prev_0 = Self::mwc_reverse(prev_0, multiplier_0);
+        prev_1 = Self::mwc_reverse(prev_1, multiplier_1);
+        return v;
+      },
+    );
+    return Ok(index.map(Location::new));
+  }
+
+  fn candidate_states(&mut self, limit: usize) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    // Picked up from a `PredictorState`, so there's no sequence, only the state it was given.
+    if self.is_solved && self.observations.is_empty() {
+      return Ok(vec![(self.conc_state_0 as u64, self.conc_state_1 as u64)]);
+    }
+    if self.observations.is_empty() {
+      return Err(Box::new(InitError::NotEnoughObservations));
+    }
+    self.reject_outliers()?;
+    let steps = self.observations.len() - 1;
+    return Ok(
+      self
+        .candidates(limit)?
+        .into_iter()
+        .map(|((multiplier_0, multiplier_1), mut state_0, mut state_1)| {
+          for _ in 0..steps {
+            state_0 = Self::mwc_forward(state_0, multiplier_0);
+            state_1 = Self::mwc_forward(state_1, multiplier_1);
+          }
+          return (state_0 as u64, state_1 as u64);
+        })
+        .collect(),
+    );
+  }
+
+  fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(self.rejected.clone().unwrap_or_default());
+  }
+
+  fn verify_observations(&mut self) -> Result<Verification, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(Verification::new(&self.observations, self.replayed.clone()));
+  }
+
+  fn state(&mut self) -> Result<PredictorState, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let mut state = PredictorState::new(
+      Engine::V8(V8Variant::Mwc1616),
+      (self.conc_state_0 as u64, self.conc_state_1 as u64),
+      (self.prev_state_0 as u64, self.prev_state_1 as u64),
+    );
+    state.multipliers = Some(self.multipliers);
+    return Ok(state);
+  }
+}
+
+impl Mwc1616Predictor {
+  #[cfg(feature = "z3")]
+  const SS_0_STR: &str = "sym_state_0";
+  #[cfg(feature = "z3")]
+  const SS_1_STR: &str = "sym_state_1";
+  // Enough exact values in a row that a window of them pins down one state, see `robust::outliers`.
+  const WINDOW_BITS: f64 = 150.0;
+  const WINDOW_LEN: usize = 3;
+
+  pub fn new(observations: Vec<Observation>, solver: SolverBackend) -> Self {
+    return Mwc1616Predictor {
+      sequence: exact_values(&observations),
+      observations,
+      is_solved: false,
+      solver,
+      multipliers: MULTIPLIERS[0],
+      conc_state_0: 0,
+      conc_state_1: 0,
+      prev_state_0: 0,
+      prev_state_1: 0,
+      allows_ambiguity: false,
+      diagnoses_unsat: true,
+      is_robust: false,
+      rejected: None,
+      replayed: vec![],
+    };
+  }
+
+  // Static 'helper' method
+  // Picks up where `state` left off, which has been checked to be one of MWC1616's.
+  pub(crate) fn from_state(state: &PredictorState) -> Self {
+    let mut predictor = Self::new(vec![], SolverBackend::Native);
+    predictor.multipliers = state.multipliers.unwrap_or(MULTIPLIERS[0]);
+    (predictor.conc_state_0, predictor.conc_state_1) = (state.state_0 as u32, state.state_1 as u32);
+    (predictor.prev_state_0, predictor.prev_state_1) =
+      (state.prev_state_0 as u32, state.prev_state_1 as u32);
+    predictor.is_solved = true;
+    return predictor;
+  }
+
+  pub fn sequence(&self) -> &[f64] {
+    return &self.sequence;
+  }
+
+  // Nothing to exhaust, so any new sequence can be swapped in.
+  pub fn reset(&mut self, new_sequence: Vec<f64>) {
+    self.is_solved = false;
+    self.rejected = None;
+    self.observations = new_sequence
+      .iter()
+      .copied()
+      .map(Observation::from)
+      .collect();
+    self.sequence = new_sequence;
+  }
+
+  // Static 'helper' method
+  fn mwc_forward(state: u32, multiplier: u32) -> u32 {
+    return multiplier * (state & 0xFFFF) + (state >> 16);
+  }
+
+  // Static 'helper' method
+  // A state made by `mwc_forward` has a carry no bigger than the multiplier, which makes this unique.
+  fn mwc_reverse(state: u32, multiplier: u32) -> u32 {
+    return ((state % multiplier) << 16) | (state / multiplier);
+  }
+
+  // Static 'helper' method
+  // `steps` of `mwc_forward` at once. A state with carry `c` and low half `x` stands for
+  // `c * 2^16 + x`, and each step multiplies that by the multiplier, modulo
+  // `multiplier * 2^16 - 1`. So `steps` of them multiply it by the multiplier to the `steps`.
+  fn mwc_jump(mut state: u32, multiplier: u32, mut steps: u64) -> u32 {
+    let modulus = ((multiplier as u64) << 16) - 1;
+    // Only a carry no bigger than the multiplier keeps the state below the modulus, which every
+    // state `mwc_forward` makes has, apart from some of the first few from an arbitrary seed.
+    while steps > 0 && state as u64 > modulus {
+      state = Self::mwc_forward(state, multiplier);
+      steps -= 1;
+    }
+    // The modulus itself steps to itself, but would be reduced to 0.
+    if state as u64 == modulus {
+      return state;
+    }
+    let (mut factor, mut power) = (1u64, multiplier as u64);
+    while steps > 0 {
+      if steps & 1 == 1 {
+        factor = factor * power % modulus;
+      }
+      power = power * power % modulus;
+      steps >>= 1;
+    }
+    return (state as u64 * factor % modulus) as u32;
+  }
+
+  // Static 'helper' method
+  fn to_output(state_0: u32, state_1: u32) -> u32 {
+    return (state_0 << 16).wrapping_add(state_1 & 0xFFFF);
+  }
+
+  // Static 'helper' method
+  fn to_double(state_0: u32, state_1: u32) -> f64 {
+    return Self::to_output(state_0, state_1) as f64 / (1u64 << 32) as f64;
+  }
+
+  // Smallest and largest output that explains each observation.
+  fn observation_bounds(&self) -> Result<Vec<(u32, u32)>, InitError> {
+    return self
+      .observations
+      .iter()
+      .map(|observation| {
+        return Self::output_bounds(observation).ok_or(InitError::Unsat);
+      })
+      .collect();
+  }
+
+  // Static 'helper' method
+  fn output_bounds(observation: &Observation) -> Option<(u32, u32)> {
+    let (low, high) = observation.bounds(32, |output| {
+      return output as f64 / (1u64 << 32) as f64;
+    })?;
+    return Some((low as u32, high as u32));
+  }
+
+  pub(crate) fn solve_symbolic_state(&mut self) -> Result<(), Box<dyn Error>> {
+    if self.is_solved {
+      return Ok(());
+    }
+    self.reject_outliers()?;
+
+    // A second candidate is all it takes to know predictions could come from the wrong state.
+    let limit = if self.allows_ambiguity { 1 } else { 2 };
+    let candidates = self.candidates(limit)?;
+    if candidates.len() > 1 {
+      return Err(Box::new(InitError::Ambiguous));
+    }
+    (self.multipliers, self.conc_state_0, self.conc_state_1) = candidates[0];
+    self.replayed = Self::outputs(candidates[0], self.observations.len());
+    Verification::new(&self.observations, self.replayed.clone()).check()?;
+
+    self.prev_state_0 = self.conc_state_0;
+    self.prev_state_1 = self.conc_state_1;
+    let (multiplier_0, multiplier_1) = self.multipliers;
+    for _ in 1..self.observations.len() {
+      self.conc_state_0 = Self::mwc_forward(self.conc_state_0, multiplier_0);
+      self.conc_state_1 = Self::mwc_forward(self.conc_state_1, multiplier_1);
+    }
+
+    self.is_solved = true;
+    return Ok(());
+  }
+
+  // Sets the observations that don't fit aside as unknown calls, once, if robust.
+  fn reject_outliers(&mut self) -> Result<(), InitError> {
+    if !self.is_robust || self.rejected.is_some() {
+      return Ok(());
+    }
+    // The native solver rules a window out far faster than z3, and takes partial observations too.
+    let rejected = outliers(
+      &self.observations,
+      Self::WINDOW_BITS,
+      |observations, limit| {
+        // Searching from an unknown first value would try every low half, so start at the window.
+        let first = observations
+          .iter()
+          .position(|observation| {
+            return *observation != Observation::Unknown;
+          })
+          .unwrap_or(0);
+        let mut predictor = Self::new(observations[first..].to_vec(), SolverBackend::Native);
+        predictor.diagnoses_unsat = false;
+        let mut candidates = predictor.candidates(limit)?;
+        for ((multiplier_0, multiplier_1), state_0, state_1) in &mut candidates {
+          for _ in 0..first {
+            *state_0 = Self::mwc_reverse(*state_0, *multiplier_0);
+            *state_1 = Self::mwc_reverse(*state_1, *multiplier_1);
+          }
+        }
+        return Ok(candidates);
+      },
+      |&candidate| {
+        return Self::outputs(candidate, self.observations.len());
+      },
+    )?;
+    for &i in &rejected {
+      self.observations[i] = Observation::Unknown;
+    }
+    self.sequence = exact_values(&self.observations);
+    self.rejected = Some(rejected);
+    return Ok(());
+  }
+
+  // Static 'helper' method
+  // What a candidate returns for its first `len` calls.
+  fn outputs(
+    ((multiplier_0, multiplier_1), mut state_0, mut state_1): Candidate,
+    len: usize,
+  ) -> Vec<f64> {
+    let mut values = vec![];
+    for i in 0..len {
+      if i > 0 {
+        state_0 = Self::mwc_forward(state_0, multiplier_0);
+        state_1 = Self::mwc_forward(state_1, multiplier_1);
+      }
+      values.push(Self::to_double(state_0, state_1));
+    }
+    return values;
+  }
+
+  // Static 'helper' method
+  // Indices of `sequence` in each of up to `streams` interleaved streams, see `untangle::untangle`.
+  pub(crate) fn untangle(sequence: &[f64], streams: usize) -> Result<Vec<Vec<usize>>, InitError> {
+    return untangle(
+      sequence,
+      streams,
+      Self::WINDOW_LEN,
+      |observations| {
+        let mut predictor = Self::new(observations.to_vec(), SolverBackend::Native);
+        predictor.diagnoses_unsat = false;
+        return predictor.candidates(MULTIPLIERS.len());
+      },
+      |&candidate, len| {
+        return Self::outputs(candidate, len);
+      },
+    );
+  }
+
+  // Static 'helper' method
+  // Which call, of `spread` in a row, returned each of `values`, see `unordered::order`.
+  pub(crate) fn order(values: &[f64], spread: usize) -> Result<Vec<usize>, InitError> {
+    return order(
+      values,
+      spread,
+      Self::WINDOW_LEN,
+      |observations| {
+        let mut predictor = Self::new(observations.to_vec(), SolverBackend::Native);
+        predictor.diagnoses_unsat = false;
+        return predictor.candidates(MULTIPLIERS.len());
+      },
+      |&candidate, len| {
+        return Self::outputs(candidate, len);
+      },
+    );
+  }
+
+  // Up to `limit` candidates, for the multipliers of every V8 release in turn.
+  fn candidates(&self, limit: usize) -> Result<Vec<Candidate>, InitError> {
+    if self.observations.is_empty() {
+      return Err(InitError::NotEnoughObservations);
+    }
+    let candidates = match self.solver {
+      SolverBackend::Native => self.native_candidates(limit),
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3 => self.z3_candidates(limit),
+    };
+    if self.diagnoses_unsat && matches!(candidates, Err(InitError::Unsat)) {
+      return Err(self.diagnose());
+    }
+    return candidates;
+  }
+
+  // Which observations are to blame for no state fitting.
+  fn diagnose(&self) -> InitError {
+    let core = match self.solver {
+      SolverBackend::Native => None,
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3 => self.z3_unsat_core(),
+    };
+    let is_possible = |observation: &Observation| {
+      return Self::output_bounds(observation).is_some();
+    };
+    return diagnose(&self.observations, core, is_possible, |observations| {
+      let mut predictor = Self::new(observations.to_vec(), self.solver);
+      predictor.diagnoses_unsat = false;
+      return Ok(predictor.candidates(2)?.len());
+    });
+  }
+
+  fn native_candidates(&self, limit: usize) -> Result<Vec<Candidate>, InitError> {
+    let bounds = self.observation_bounds()?;
+    let mut candidates = vec![];
+
+    for (multiplier_0, multiplier_1) in MULTIPLIERS {
+      // The high half of an output is the low 16 bits of `state_0`, whatever `state_1` is.
+      let high_ranges: Vec<(u32, u32)> = bounds
+        .iter()
+        .map(|&(low, high)| {
+          return (low >> 16, high >> 16);
+        })
+        .collect();
+
+      for state_0 in Self::solve_half(&high_ranges, multiplier_0) {
+        let low_ranges = Self::low_ranges(&bounds, state_0, multiplier_0);
+        for state_1 in Self::solve_half(&low_ranges, multiplier_1) {
+          candidates.push(((multiplier_0, multiplier_1), state_0, state_1));
+          if candidates.len() >= limit {
+            return Ok(candidates);
+          }
+        }
+      }
+    }
+
+    if candidates.is_empty() {
+      return Err(InitError::Unsat);
+    }
+    return Ok(candidates);
+  }
+
+  // Static 'helper' method
+  // The low half only matters where the high half sits on the edge of an observation's range.
+  fn low_ranges(bounds: &[(u32, u32)], state_0: u32, multiplier_0: u32) -> Vec<(u32, u32)> {
+    let mut next_0 = state_0;
+    return bounds
+      .iter()
+      .enumerate()
+      .map(|(i, &(low, high))| {
+        if i > 0 {
+          next_0 = Self::mwc_forward(next_0, multiplier_0);
+        }
+        let high_half = next_0 & 0xFFFF;
+        let min = if high_half == low >> 16 {
+          low & 0xFFFF
+        } else {
+          0
+        };
+        let max = if high_half == high >> 16 {
+          high & 0xFFFF
+        } else {
+          0xFFFF
+        };
+        return (min, max);
+      })
+      .collect();
+  }
+
+  // Static 'helper' method
+  // States behind the first of consecutive values, whose low 16 bits stay within `ranges`. An exact
+  // value leaves only the carry to search.
+  fn solve_half(ranges: &[(u32, u32)], multiplier: u32) -> impl Iterator<Item = u32> + '_ {
+    let (&(first_min, first_max), rest) = ranges.split_first().unwrap_or((&(0, 0), &[]));
+    return (first_min..=first_max)
+      .flat_map(move |low| {
+        return (0..=multiplier).map(move |carry| {
+          return (carry << 16) | low;
+        });
+      })
+      .filter(move |&state| {
+        let mut next = state;
+        return rest.iter().all(|&(min, max)| {
+          next = Self::mwc_forward(next, multiplier);
+          let low = next & 0xFFFF;
+          return min <= low && low <= max;
+        });
+      });
+  }
+
+  #[cfg(feature = "z3")]
+  fn z3_candidates(&self, limit: usize) -> Result<Vec<Candidate>, InitError> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+
+    let bounds = self.observation_bounds()?;
+    let base_state_0 = BV::new_const(&context, Self::SS_0_STR, 32);
+    let base_state_1 = BV::new_const(&context, Self::SS_1_STR, 32);
+
+    let mut candidates = vec![];
+    for multipliers in MULTIPLIERS {
+      solver.push();
+      Self::constrain_carries(
+        &context,
+        &solver,
+        (&base_state_0, &base_state_1),
+        multipliers,
+      );
+      let states = (base_state_0.clone(), base_state_1.clone());
+      for constraint in Self::output_constraints(&context, states, &bounds, multipliers) {
+        solver.assert(&constraint);
+      }
+
+      while candidates.len() < limit && solver.check() == SatResult::Sat {
+        let model = solver.get_model().ok_or(InitError::MissingModel)?;
+
+        let state_0 = model
+          .eval(&base_state_0, true)
+          .ok_or(InitError::EvalFailed(Self::SS_0_STR))?
+          .as_u64()
+          .ok_or(InitError::ConvertFailed(Self::SS_0_STR))?;
+
+        let state_1 = model
+          .eval(&base_state_1, true)
+          .ok_or(InitError::EvalFailed(Self::SS_1_STR))?
+          .as_u64()
+          .ok_or(InitError::ConvertFailed(Self::SS_1_STR))?;
+
+        // Rule this state out, so the next check finds another one if there is one.
+        solver.assert(&Bool::or(
+          &context,
+          &[
+            &base_state_0._eq(&BV::from_u64(&context, state_0, 32)).not(),
+            &base_state_1._eq(&BV::from_u64(&context, state_1, 32)).not(),
+          ],
+        ));
+        candidates.push((multipliers, state_0 as u32, state_1 as u32));
+      }
+      solver.pop(1);
+
+      if candidates.len() >= limit {
+        break;
+      }
+    }
+
+    if candidates.is_empty() {
+      return Err(InitError::Unsat);
+    }
+    return Ok(candidates);
+  }
+
+  // The smallest set of observations that z3 found can't all be right, with any multipliers.
+  #[cfg(feature = "z3")]
+  fn z3_unsat_core(&self) -> Option<Vec<usize>> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+
+    let bounds = self.observation_bounds().ok()?;
+    let base_state_0 = BV::new_const(&context, Self::SS_0_STR, 32);
+    let base_state_1 = BV::new_const(&context, Self::SS_1_STR, 32);
+
+    let mut smallest: Option<Vec<usize>> = None;
+    for multipliers in MULTIPLIERS {
+      solver.push();
+      Self::constrain_carries(
+        &context,
+        &solver,
+        (&base_state_0, &base_state_1),
+        multipliers,
+      );
+      let states = (base_state_0.clone(), base_state_1.clone());
+      let constraints = Self::output_constraints(&context, states, &bounds, multipliers);
+      let core = unsat_core(&context, &solver, &constraints);
+      solver.pop(1);
+
+      let core = core?;
+      if smallest.as_ref().is_none_or(|smallest| {
+        return core.len() < smallest.len();
+      }) {
+        smallest = Some(core);
+      }
+    }
+    return smallest;
+  }
+
+  // Static 'helper' method
+  // Same as `solve_half`, a carry is never more than its multiplier.
+  #[cfg(feature = "z3")]
+  fn constrain_carries(
+    context: &Context,
+    solver: &Solver,
+    (state_0, state_1): (&BV, &BV),
+    (multiplier_0, multiplier_1): (u32, u32),
+  ) {
+    for (state, multiplier) in [(state_0, multiplier_0), (state_1, multiplier_1)] {
+      let carry = state.bvlshr(&BV::from_u64(context, 16, 32));
+      solver.assert(&carry.bvule(&BV::from_u64(context, multiplier as u64, 32)));
+    }
+  }
+
+  // Static 'helper' method
+  // A constraint for each observation, on the outputs from the states behind the first one.
+  #[cfg(feature = "z3")]
+  fn output_constraints<'a>(
+    context: &'a Context,
+    (mut sym_state_0, mut sym_state_1): (BV<'a>, BV<'a>),
+    bounds: &[(u32, u32)],
+    (multiplier_0, multiplier_1): (u32, u32),
+  ) -> Vec<Bool<'a>> {
+    let mut constraints = vec![];
+    for (i, &(low, high)) in bounds.iter().enumerate() {
+      if i > 0 {
+        sym_state_0 = Self::mwc_symbolic(context, &sym_state_0, multiplier_0);
+        sym_state_1 = Self::mwc_symbolic(context, &sym_state_1, multiplier_1);
+      }
+      let output = sym_state_0
+        .bvshl(&BV::from_u64(context, 16, 32))
+        .bvadd(&sym_state_1.bvand(&BV::from_u64(context, 0xFFFF, 32)));
+      constraints.push(Bool::and(
+        context,
+        &[
+          &output.bvuge(&BV::from_u64(context, low as u64, 32)),
+          &output.bvule(&BV::from_u64(context, high as u64, 32)),
+        ],
+      ));
+    }
+    return constraints;
+  }
+
+  // Static 'helper' method
+  #[cfg(feature = "z3")]
+  fn mwc_symbolic<'a>(context: &'a Context, state: &BV<'a>, multiplier: u32) -> BV<'a> {
+    let low = state.bvand(&BV::from_u64(context, 0xFFFF, 32));
+    let carry = state.bvlshr(&BV::from_u64(context, 16, 32));
+    return BV::from_u64(context, multiplier as u64, 32)
+      .bvmul(&low)
+      .bvadd(&carry);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Mwc1616Predictor;
+  use crate::{Location, Observation, Predictor, SolverBackend, errors::InitError};
+  use std::error::Error;
+
+  fn mwc1616_values(multipliers: (u32, u32), count: usize) -> Vec<f64> {
+    let (mut state_0, mut state_1) = (0x4B1D_2C3Eu32, 0x7F00_0A11u32);
+    let mut values = vec![];
+    for _ in 0..count {
+      state_0 = Mwc1616Predictor::mwc_forward(state_0, multipliers.0);
+      state_1 = Mwc1616Predictor::mwc_forward(state_1, multipliers.1);
+      values.push(Mwc1616Predictor::to_double(state_0, state_1));
+    }
+    return values;
+  }
+
+  fn exact(values: &[f64]) -> Vec<Observation> {
+    return values.iter().copied().map(Observation::from).collect();
+  }
+
+  // What V8 returns from the state `mwc1616_values` starts at, for each of `MULTIPLIERS`. The
+  // `math.js` ones are V8's `MathRandom` run as it shipped, on a JS engine. The last is V8 3.x's
+  // C++ `random_base`, through `FillHeapNumberWithRandom`, which ORs the 32 bits into the mantissa
+  // of 2^20 and subtracts 2^20 again.
+  const V8_OUTPUTS: [((u32, u32), [f64; 8]); 3] = [
+    (
+      (18030, 36969),
+      [
+        0.2568540556821972,
+        0.07462709001265466,
+        0.38774788146838546,
+        0.9923636787571013,
+        0.2735627971123904,
+        0.5520660406909883,
+        0.7709426693618298,
+        0.11098456825129688,
+      ],
+    ),
+    (
+      (18273, 36969),
+      [
+        0.2523679716978222,
+        0.5158960109110922,
+        0.825995562132448,
+        0.4366233223117888,
+        0.4960206828545779,
+        0.8487121588550508,
+        0.5998000912368298,
+        0.24463630164973438,
+      ],
+    ),
+    (
+      (36969, 18273),
+      [
+        0.3146213556174189,
+        0.3202025070786476,
+        0.2974725018721074,
+        0.3524812483228743,
+        0.9279982482548803,
+        0.20067839324474335,
+        0.031162600265815854,
+        0.009850537870079279,
+      ],
+    ),
+  ];
+
+  #[test]
+  fn matches_what_v8_returned() -> Result<(), Box<dyn Error>> {
+    for (multipliers, outputs) in V8_OUTPUTS {
+      assert_eq!(mwc1616_values(multipliers, outputs.len()), outputs);
+
+      let mut mwc = Mwc1616Predictor::new(exact(&outputs[..3]), SolverBackend::Native);
+      for expected in &outputs[3..] {
+        assert_eq!(mwc.predict_next()?, *expected);
+      }
+      assert_eq!(mwc.multipliers, multipliers);
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn detects_multipliers_of_older_releases() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18273, 36969), 12);
+    let mut mwc = Mwc1616Predictor::new(exact(&values[..3]), SolverBackend::Native);
+    for expected in &values[3..] {
+      assert_eq!(mwc.predict_next()?, *expected);
+    }
+    assert_eq!(mwc.multipliers, (18273, 36969));
+    return Ok(());
+  }
+
+  #[test]
+  fn solves_around_calls_it_did_not_see() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18030, 36969), 20);
+    let observations = Observation::sparse([0, 4, 9].map(|i| {
+      return (i, values[i].into());
+    }));
+    let mut mwc = Mwc1616Predictor::new(observations, SolverBackend::Native);
+    for expected in &values[10..] {
+      assert_eq!(mwc.predict_next()?, *expected);
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn solves_from_integers() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18030, 36969), 60);
+    let rolls: Vec<Observation> = values[..40]
+      .iter()
+      .map(|value| {
+        return Observation::integer((value * 1000.0).floor() as i64, 1000);
+      })
+      .collect::<Result<_, _>>()?;
+
+    // Integers only say something about the top of each output, so the rest is a best guess.
+    let mut mwc = Mwc1616Predictor::new(rolls, SolverBackend::Native);
+    let error = mwc
+      .solve_symbolic_state()
+      .err()
+      .ok_or("solved an ambiguous sequence")?;
+    assert!(matches!(
+      error.downcast_ref::<InitError>(),
+      Some(InitError::Ambiguous)
+    ));
+    mwc.allows_ambiguity = true;
+    for expected in &values[40..] {
+      assert_eq!(
+        (mwc.predict_next()? * 1000.0).floor(),
+        (expected * 1000.0).floor()
+      );
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn rejects_values_not_from_mwc1616() {
+    let mut mwc = Mwc1616Predictor::new(exact(&[0.1, 0.2, 0.3, 0.4]), SolverBackend::Native);
+    assert!(mwc.predict_next().is_err());
+  }
+
+  #[test]
+  fn asks_for_observations_when_there_are_none() {
+    let mut mwc = Mwc1616Predictor::new(vec![], SolverBackend::Native);
+    let error = mwc.candidate_states(2).err();
+    assert!(matches!(
+      error.as_deref().and_then(|e| {
+        return e.downcast_ref::<InitError>();
+      }),
+      Some(InitError::NotEnoughObservations)
+    ));
+  }
+
+  #[test]
+  fn names_the_value_that_does_not_fit() -> Result<(), Box<dyn Error>> {
+    let mut values = mwc1616_values((18030, 36969), 6);
+    values[4] = 0.3819660112501051;
+
+    let solvers = [
+      SolverBackend::Native,
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3,
+    ];
+    for solver in solvers {
+      let mut mwc = Mwc1616Predictor::new(exact(&values), solver);
+      let error = mwc
+        .predict_next()
+        .err()
+        .ok_or("solved a sequence that doesn't fit")?;
+      match error.downcast_ref::<InitError>() {
+        Some(InitError::Inconsistent { indices, culprit }) => {
+          assert!(indices.contains(&4));
+          assert_eq!(*culprit, Some(4));
+        }
+        _ => return Err(error),
+      }
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn robust_mode_leaves_out_values_from_elsewhere() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18030, 36969), 16);
+    let mut sequence = values[..12].to_vec();
+    sequence[2] = 0.3819660112501051;
+    sequence[9] = 0.6180339887498949;
+
+    let mut mwc = Mwc1616Predictor::new(exact(&sequence), SolverBackend::Native);
+    mwc.is_robust = true;
+    for expected in &values[12..] {
+      assert_eq!(mwc.predict_next()?, *expected);
+    }
+    assert_eq!(mwc.rejected_indices()?, vec![2, 9]);
+    return Ok(());
+  }
+
+  #[test]
+  fn untangles_streams_of_two_contexts() -> Result<(), Box<dyn Error>> {
+    // Far enough apart in one generator to stand in for another context.
+    let values = mwc1616_values((18030, 36969), 40);
+    let (first, second) = (&values[..6], &values[20..]);
+    let sequence = [
+      first[0], first[1], second[0], first[2], second[1], second[2], first[3], second[3],
+      second[4], first[4], first[5], second[5],
+    ];
+
+    let members = Mwc1616Predictor::untangle(&sequence, 2)?;
+    assert_eq!(
+      members,
+      vec![vec![0, 1, 3, 6, 9, 10], vec![2, 4, 5, 7, 8, 11]]
+    );
+    return Ok(());
+  }
+
+  #[test]
+  fn jumps_ahead_without_stepping() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18030, 36969), 5000);
+    let mut mwc = Mwc1616Predictor::new(exact(&values[..3]), SolverBackend::Native);
+    assert_eq!(mwc.predict_at(4996)?, values[4999]);
+
+    mwc.skip(1000)?;
+    assert_eq!(mwc.predict_next()?, values[1003]);
+    assert!(mwc.predict_at(u64::MAX).is_err());
+    return Ok(());
+  }
+
+  #[test]
+  fn locates_values_either_way() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18030, 36969), 2000);
+    let mut mwc = Mwc1616Predictor::new(exact(&values[1000..1003]), SolverBackend::Native);
+    assert_eq!(
+      mwc.find_index(values[1500], 1000)?,
+      Some(Location::new(497))
+    );
+    assert_eq!(mwc.find_index(values[10], 1000)?, Some(Location::new(-993)));
+    return Ok(());
+  }
+
+  #[test]
+  fn picks_up_from_a_saved_state() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18273, 36969), 10);
+    let mut mwc = Mwc1616Predictor::new(exact(&values[..4]), SolverBackend::Native);
+    let state = mwc.state()?;
+    assert_eq!(state.multipliers, Some((18273, 36969)));
+
+    let mut restored = Mwc1616Predictor::from_state(&state);
+    for expected in &values[4..] {
+      assert_eq!(restored.predict_next()?, *expected);
+    }
+    return Ok(());
+  }
+
+  #[test]
+  fn orders_values_collected_out_of_order() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((18030, 36969), 6);
+    let bag = [values[4], values[0], values[5], values[2], values[1]];
+
+    assert_eq!(Mwc1616Predictor::order(&bag, 7)?, vec![4, 0, 5, 2, 1]);
+    return Ok(());
+  }
+
+  #[cfg(feature = "z3")]
+  #[test]
+  fn native_and_z3_solvers_agree() -> Result<(), Box<dyn Error>> {
+    let values = mwc1616_values((36969, 18273), 4);
+    let mut native = Mwc1616Predictor::new(exact(&values), SolverBackend::Native);
+    let mut z3 = Mwc1616Predictor::new(exact(&values), SolverBackend::Z3);
+    for _ in 0..10 {
+      assert_eq!(native.predict_next()?, z3.predict_next()?);
+    }
+    assert_eq!(native.predict_previous()?, z3.predict_previous()?);
+    return Ok(());
+  }
+}
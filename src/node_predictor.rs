@@ -1,205 +1,35 @@
-use crate::{NodeJsMajorVersion, Predictor, errors::*};
-use std::{
-  error::Error,
-  sync::{Arc, Mutex},
+use crate::{
+  V8Version,
+  v8_predictor::{V8Engine, v8_predictor},
 };
-use z3::{Config, Context, SatResult, Solver, ast::*};
 
+// Takes a `NodeJsMajorVersion`, or the `V8Version` from `Runtime::Node.v8_version("v22.3.1")`.
+// `from_seed` predicts a run started with `--random-seed` (or Deno's `--seed`).
+#[derive(Clone)]
 pub struct NodePredictor {
-  sequence: Vec<f64>,
-  internal_sequence: Vec<f64>,
-  is_solved: bool,
-  node_js_major_version: NodeJsMajorVersion,
-  conc_state_0: u64,
-  conc_state_1: u64,
-  num_predictions_made: Arc<Mutex<u8>>,
+  v8: V8Engine,
+  // Exported with the state, `None` if it was picked up from a state that didn't say.
+  version: Option<V8Version>,
 }
 
-impl Predictor for NodePredictor {
-  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
-    self.increment_prediction_count()?;
-    self.solve_symbolic_state()?;
-    let v = self.xor_shift_128_plus_concrete();
-    let p = self.to_double(v);
-    return Ok(p);
-  }
-}
-
-impl NodePredictor {
-  pub const MAX_NUM_PREDICTIONS: u8 = 64;
-  const SS_0_STR: &str = "sym_state_0";
-  const SS_1_STR: &str = "sym_state_1";
-
-  pub fn new(node_js_major_version: NodeJsMajorVersion, seq: Vec<f64>) -> Self {
-    let len = seq.len() as u8;
-    let mut iseq = seq.clone();
-    iseq.reverse();
-
-    return NodePredictor {
-      internal_sequence: iseq,
-      sequence: seq,
-      node_js_major_version,
-      conc_state_0: 0,
-      conc_state_1: 0,
-      num_predictions_made: Arc::new(Mutex::new(len)),
-      is_solved: false,
-    };
-  }
-
-  #[allow(dead_code)]
-  pub fn sequence(&self) -> &[f64] {
-    return &self.sequence;
-  }
-
-  // So consumers don't have to import the Predictor trait as well as the struct.
-  pub fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
-    return <Self as Predictor>::predict_next(self);
-  }
-
-  fn xor_shift_128_plus_concrete(&mut self) -> u64 {
-    let result = self.conc_state_0;
-    let t1 = self.conc_state_0;
-    let mut t0 = self.conc_state_1 ^ (self.conc_state_0 >> 26);
-    t0 ^= self.conc_state_0;
-    t0 ^= (t0 >> 17) ^ (t0 >> 34) ^ (t0 >> 51);
-    t0 ^= (t0 << 23) ^ (t0 << 46);
-    self.conc_state_0 = t0;
-    self.conc_state_1 = t1;
-    return result;
-  }
-
-  fn to_double(&self, value: u64) -> f64 {
-    if self.node_js_major_version as u8 >= 24 {
-      return (value >> 11) as f64 / (1u64 << 53) as f64;
-    }
-    return f64::from_bits((value >> 12) | 0x3FF0000000000000) - 1.0;
-  }
-
-  // If our count is below the max, we can increment, otherwise error.
-  fn increment_prediction_count(&self) -> Result<(), PredictionLimitError> {
-    let mut c = self.num_predictions_made.lock()?;
-    if *c >= Self::MAX_NUM_PREDICTIONS {
-      return Err(PredictionLimitError);
-    }
-    *c += 1;
-    return Ok(());
-  }
-
-  #[allow(dead_code)]
-  fn reset(&mut self, new_sequence: Vec<f64>) -> Result<(), PredictionLimitError> {
-    let mut c = self.num_predictions_made.lock()?;
-    if *c < Self::MAX_NUM_PREDICTIONS {
-      return Ok(());
-    }
-    *c = new_sequence.len() as u8;
-    self.is_solved = false;
-    self.internal_sequence = new_sequence.to_vec();
-    self.sequence = new_sequence.to_vec();
-    self.internal_sequence.reverse();
-    return Ok(());
-  }
-
-  fn solve_symbolic_state(&mut self) -> Result<(), InitError> {
-    if self.is_solved {
-      return Ok(());
-    }
-
-    let config = Config::new();
-    let context = Context::new(&config);
-    let solver = Solver::new(&context);
-
-    let mut sym_state_0 = BV::new_const(&context, Self::SS_0_STR, 64);
-    let mut sym_state_1 = BV::new_const(&context, Self::SS_1_STR, 64);
-
-    for &observed in &self.internal_sequence {
-      Self::xor_shift_128_plus_symbolic(&context, &mut sym_state_0, &mut sym_state_1);
-      Self::constrain_mantissa(
-        observed,
-        self.node_js_major_version,
-        &context,
-        &solver,
-        &sym_state_0,
-      );
-    }
-
-    if solver.check() != SatResult::Sat {
-      return Err(InitError::Unsat);
-    }
-
-    let model = solver.get_model().ok_or(InitError::MissingModel)?;
-
-    self.conc_state_0 = model
-      .eval(&sym_state_0, true)
-      .ok_or(InitError::EvalFailed(Self::SS_0_STR))?
-      .as_u64()
-      .ok_or(InitError::ConvertFailed(Self::SS_0_STR))?;
-
-    self.conc_state_1 = model
-      .eval(&sym_state_1, true)
-      .ok_or(InitError::EvalFailed(Self::SS_1_STR))?
-      .as_u64()
-      .ok_or(InitError::ConvertFailed(Self::SS_1_STR))?;
-
-    for _ in 0..self.internal_sequence.len() {
-      self.xor_shift_128_plus_concrete();
-    }
-
-    self.is_solved = true;
-    return Ok(());
-  }
-
-  // Static 'helper' method
-  fn xor_shift_128_plus_symbolic<'a>(
-    context: &'a Context,
-    state_0: &mut BV<'a>,
-    state_1: &mut BV<'a>,
-  ) {
-    let state_0_shifted_left = state_0.bvshl(&BV::from_u64(context, 23, 64));
-    let mut s1 = &*state_0 ^ state_0_shifted_left;
-    let s1_shifted_right = s1.bvlshr(&BV::from_u64(context, 17, 64));
-
-    s1 ^= s1_shifted_right;
-    s1 ^= state_1.clone();
-    s1 ^= state_1.bvlshr(&BV::from_u64(context, 26, 64));
-
-    std::mem::swap(state_0, state_1);
-    *state_1 = s1;
-  }
-
-  // Static 'helper' method
-  fn constrain_mantissa(
-    value: f64,
-    nodejs_version: NodeJsMajorVersion,
-    context: &Context,
-    solver: &Solver,
-    state_0: &BV,
-  ) {
-    if nodejs_version as u8 >= 24 {
-      // Recover mantissa
-      let mantissa = (value * (1u64 << 53) as f64) as u64;
-      // Add mantissa constraint
-      solver.assert(
-        &state_0
-          .bvlshr(&BV::from_u64(context, 11, 64))
-          ._eq(&BV::from_u64(context, mantissa, 64)),
-      );
-    } else {
-      // Recover mantissa
-      let mantissa = f64::to_bits(value + 1.0) & ((1u64 << 52) - 1);
-      // Add mantissa constraint
-      solver.assert(
-        &BV::from_u64(context, mantissa, 64)._eq(&state_0.bvlshr(&BV::from_u64(context, 12, 64))),
-      );
-    }
-  }
-}
+v8_predictor!(NodePredictor);
 
 #[cfg(test)]
 mod tests {
   mod general {
-    use crate::{NodePredictor, errors::PredictionLimitError};
+    use crate::{
+      Linkage, Location, NodeJsMajorVersion, NodePredictor, Observation, SolverBackend,
+      errors::{InitError, PredictionLimitError},
+      v8_predictor::{
+        XorShiftVariant,
+        test_values::{v8_values, v8_values_from},
+      },
+    };
     use std::error::Error;
 
+    // Another context's generator, for a stream that isn't `v8_values`.
+    const OTHER_STATE: (u64, u64) = (0x0123456789ABCDEF, 0xFEDCBA9876543210);
+
     #[test]
     fn reset_after_exhaustion() -> Result<(), Box<dyn Error>> {
       let seq_first = vec![
@@ -299,7 +129,7 @@ mod tests {
 
       let seq_first_len = seq_first.len();
 
-      let mut np = NodePredictor::new(crate::NodeJsMajorVersion::V24, seq_first);
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, seq_first);
 
       let mut first_predictions = vec![];
 
@@ -330,6 +160,547 @@ mod tests {
       assert_eq!(second_preds, exp_second);
       return Ok(());
     }
+
+    #[test]
+    fn history_crosses_into_previous_pool() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(128);
+
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, values[64..68].to_vec());
+
+      assert_eq!(np.history(70)?[6..], values[..64]);
+      return Ok(());
+    }
+
+    #[test]
+    fn pool_aware_predictions_continue_across_refills() -> Result<(), Box<dyn Error>> {
+      let expected = v8_values(192);
+
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, expected[..4].to_vec());
+      np.set_pool_aware(true);
+
+      let mut predictions = vec![];
+      for _ in 4..expected.len() {
+        predictions.push(np.predict_next()?);
+      }
+
+      assert_eq!(predictions, expected[4..]);
+      return Ok(());
+    }
+
+    #[test]
+    fn solves_sequence_starting_inside_pool() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(128);
+
+      // Other code used up 60 values before we started observing, so we straddle the refill.
+      let sequence = values[60..66].to_vec();
+
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, sequence);
+
+      assert_eq!(np.pool_offset()?, 60);
+      assert_eq!(np.pool_index()?, 2);
+      for expected in &values[66..76] {
+        assert_eq!(np.predict_next()?, *expected);
+      }
+      assert_eq!(np.history(6)?, values[54..60]);
+      return Ok(());
+    }
+
+    #[test]
+    fn solves_around_calls_it_did_not_see() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(128);
+
+      // Starts part way into the pool and crosses into the next one, calls 0 to 49 went elsewhere.
+      // The last call of the pool and the first of the next pin down where the refill happened.
+      let observations = Observation::sparse([50, 53, 57, 63, 64, 70].map(|i| {
+        return (i, values[i].into());
+      }));
+      let mut np = NodePredictor::from_observations(
+        NodeJsMajorVersion::V24,
+        observations,
+        SolverBackend::Native,
+      );
+      assert_eq!(np.pool_offset()?, 50);
+      for expected in &values[71..90] {
+        assert_eq!(np.predict_next()?, *expected);
+      }
+      assert_eq!(np.predict_previous()?, values[49]);
+      return Ok(());
+    }
+
+    #[test]
+    fn solves_from_dice_rolls() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(192);
+
+      // `Math.floor(Math.random() * 6) + 1`, under 3 bits each, so it takes a lot of them.
+      let rolls = values[..120]
+        .iter()
+        .map(|value| {
+          return Observation::Integer {
+            value: (value * 6.0).floor() as i64 + 1,
+            range: 6,
+            offset: 1,
+          };
+        })
+        .collect();
+
+      let mut np =
+        NodePredictor::from_observations(NodeJsMajorVersion::V24, rolls, SolverBackend::Native);
+      np.set_pool_aware(true);
+      for expected in &values[120..150] {
+        assert_eq!(np.predict_next()?, *expected);
+      }
+      return Ok(());
+    }
+
+    #[test]
+    fn solves_from_rounded_and_float32_values() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(18);
+
+      // `value.toFixed(6)`, and what a `Float32Array` keeps, both lose bits but predict exactly.
+      let rounded: Vec<Observation> = values[..8]
+        .iter()
+        .map(|value| {
+          return Observation::rounded((value * 1e6).round() / 1e6, 6);
+        })
+        .collect();
+      let float32: Vec<Observation> = values[..6]
+        .iter()
+        .map(|value| {
+          return Observation::Float32(*value as f32);
+        })
+        .collect();
+
+      for (observations, observed) in [(rounded, 8), (float32, 6)] {
+        let mut np = NodePredictor::from_observations(
+          NodeJsMajorVersion::V24,
+          observations,
+          SolverBackend::Native,
+        );
+        for expected in &values[observed..observed + 10] {
+          assert_eq!(np.predict_next()?, *expected);
+        }
+      }
+      return Ok(());
+    }
+
+    #[test]
+    fn asks_for_more_integers_when_too_few() -> Result<(), Box<dyn Error>> {
+      let rolls = vec![Observation::integer(3, 6)?; 10];
+      let mut np =
+        NodePredictor::from_observations(NodeJsMajorVersion::V24, rolls, SolverBackend::Native);
+      let error = np.predict_next().err();
+      assert!(matches!(
+        error.as_deref().and_then(|e| {
+          return e.downcast_ref::<InitError>();
+        }),
+        Some(InitError::NotEnoughObservations)
+      ));
+      return Ok(());
+    }
+
+    #[test]
+    fn reports_ambiguity_when_too_short() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(5);
+
+      // Two doubles and a 10 bit integer leave a dozen bits of the state unknown.
+      let observations = vec![
+        Observation::Exact(values[0]),
+        Observation::Exact(values[1]),
+        Observation::integer((values[2] * 1024.0).floor() as i64, 1024)?,
+      ];
+      let mut np = NodePredictor::from_observations(
+        NodeJsMajorVersion::V24,
+        observations.clone(),
+        SolverBackend::Native,
+      );
+      let error = np.predict_next().err();
+      assert!(matches!(
+        error.as_deref().and_then(|e| {
+          return e.downcast_ref::<InitError>();
+        }),
+        Some(InitError::Ambiguous)
+      ));
+
+      let mut candidates = np.candidate_states(8)?;
+      assert_eq!(candidates.len(), 8);
+      candidates.dedup();
+      assert_eq!(candidates.len(), 8);
+
+      // One more double pins it down.
+      let mut observations = observations;
+      observations.push(Observation::Exact(values[3]));
+      let mut np = NodePredictor::from_observations(
+        NodeJsMajorVersion::V24,
+        observations,
+        SolverBackend::Native,
+      );
+      assert_eq!(np.candidate_states(2)?.len(), 1);
+      assert_eq!(np.predict_next()?, values[4]);
+      return Ok(());
+    }
+
+    #[test]
+    fn names_the_value_that_does_not_fit() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(6);
+
+      // A value pasted from another tab.
+      let mut sequence = values[..6].to_vec();
+      sequence[3] = 0.6180339887498949;
+
+      let mut np =
+        NodePredictor::with_solver(NodeJsMajorVersion::V24, sequence, SolverBackend::Native);
+      let error = np
+        .predict_next()
+        .err()
+        .ok_or("solved a sequence that doesn't fit")?;
+      match error.downcast_ref::<InitError>() {
+        Some(InitError::Inconsistent { indices, culprit }) => {
+          assert_eq!(*indices, vec![3]);
+          assert_eq!(*culprit, Some(3));
+        }
+        _ => return Err(error),
+      }
+      return Ok(());
+    }
+
+    #[test]
+    fn names_an_early_value_that_does_not_fit() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(6);
+
+      // The later values fit on their own, so they're not the ones to blame.
+      let mut sequence = values[..6].to_vec();
+      sequence[1] = 0.6180339887498949;
+
+      let mut np =
+        NodePredictor::with_solver(NodeJsMajorVersion::V24, sequence, SolverBackend::Native);
+      let error = np
+        .predict_next()
+        .err()
+        .ok_or("solved a sequence that doesn't fit")?;
+      match error.downcast_ref::<InitError>() {
+        Some(InitError::Inconsistent { indices, culprit }) => {
+          assert_eq!(*indices, vec![1]);
+          assert_eq!(*culprit, Some(1));
+        }
+        _ => return Err(error),
+      }
+      return Ok(());
+    }
+
+    #[test]
+    fn names_the_last_value_that_does_not_fit() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(4);
+
+      // Without the last value, the rest fit more than one place in the cache, until it's left
+      // out altogether.
+      let mut sequence = values[..4].to_vec();
+      sequence.push(0.5);
+
+      let mut np =
+        NodePredictor::with_solver(NodeJsMajorVersion::V24, sequence, SolverBackend::Native);
+      let error = np
+        .predict_next()
+        .err()
+        .ok_or("solved a sequence that doesn't fit")?;
+      match error.downcast_ref::<InitError>() {
+        Some(InitError::Inconsistent { indices, culprit }) => {
+          assert_eq!(*indices, vec![4]);
+          assert_eq!(*culprit, Some(4));
+        }
+        _ => return Err(error),
+      }
+      return Ok(());
+    }
+
+    #[test]
+    fn robust_mode_leaves_out_values_from_elsewhere() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(128);
+
+      // Scraped from a log that also has values from another process, across a refill.
+      let mut sequence = values[54..68].to_vec();
+      sequence[2] = 0.6180339887498949;
+      sequence[11] = 0.3819660112501051;
+
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, sequence);
+      np.set_robust(true);
+
+      assert_eq!(np.rejected_indices()?, vec![2, 11]);
+      assert_eq!(np.pool_offset()?, 54);
+      for expected in &values[68..76] {
+        assert_eq!(np.predict_next()?, *expected);
+      }
+
+      // What the other process's values replaced, from the state the rest fit.
+      let verification = np.verify_observations()?;
+      assert!(verification.is_verified());
+      assert_eq!(verification.replayed[2], values[56]);
+      assert_eq!(verification.replayed[11], values[65]);
+      return Ok(());
+    }
+
+    #[test]
+    fn robust_mode_finds_the_largest_subset_with_no_clean_run() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(38);
+
+      // Every 4 values in a row have one from elsewhere, so no run of them fits on its own.
+      let mut sequence = values[20..34].to_vec();
+      sequence[3] = 0.6180339887498949;
+      sequence[7] = 0.3819660112501051;
+      sequence[11] = 0.4142135623730951;
+
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, sequence);
+      np.set_robust(true);
+
+      assert_eq!(np.rejected_indices()?, vec![3, 7, 11]);
+      for expected in &values[34..38] {
+        assert_eq!(np.predict_next()?, *expected);
+      }
+      return Ok(());
+    }
+
+    #[test]
+    fn untangles_streams_of_two_contexts() -> Result<(), Box<dyn Error>> {
+      // One context's stream crosses a refill, the other's starts a fresh cache.
+      let first = v8_values(128)[58..].to_vec();
+      let second = v8_values_from(OTHER_STATE, XorShiftVariant::Mantissa53, 64);
+
+      let assignment = [0, 0, 1, 0, 1, 1, 0, 1, 0, 0, 1, 0, 1, 1, 0, 0, 1, 0];
+      let (mut firsts, mut seconds) = (first.iter(), second.iter());
+      let sequence = assignment
+        .iter()
+        .map(|&stream| {
+          let values = if stream == 0 {
+            &mut firsts
+          } else {
+            &mut seconds
+          };
+          return values.next().copied().ok_or("ran out of values");
+        })
+        .collect::<Result<Vec<f64>, _>>()?;
+
+      let mut untangled = NodePredictor::untangle(NodeJsMajorVersion::V24, sequence, 2)?;
+      assert_eq!(untangled.assignment, assignment);
+      assert_eq!(untangled.streams[0].predict_next()?, first[10]);
+      assert_eq!(untangled.streams[1].predict_next()?, second[8]);
+      return Ok(());
+    }
+
+    #[test]
+    fn jumps_past_the_cache() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(256);
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, values[..4].to_vec());
+
+      // Read 204 of the first cache's fill, which is in the fourth cache.
+      assert_eq!(np.predict_at(200)?, values[204]);
+      assert_eq!(np.predict_at(59)?, values[63]);
+      assert_eq!(np.predict_at(60)?, values[64]);
+
+      np.skip(130)?;
+      assert_eq!(np.pool_index()?, 6);
+      assert_eq!(np.predict_next()?, values[134]);
+      np.skip(56)?;
+      assert_eq!(np.predict_next()?, values[191]);
+      // The cache is used up, as it would be after predicting every value in it.
+      assert!(np.predict_next().is_err());
+      // Past the last call a `u64` counts from here.
+      assert!(np.skip(u64::MAX).is_err());
+      return Ok(());
+    }
+
+    #[test]
+    fn locates_values_in_other_caches() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(256);
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, values[64..68].to_vec());
+
+      assert_eq!(
+        np.find_index(values[202], 1000)?,
+        Some(Location {
+          index: 134,
+          pool: Some(2),
+          slot: Some(10),
+        })
+      );
+      assert_eq!(
+        np.find_index(values[60], 1000)?,
+        Some(Location {
+          index: -8,
+          pool: Some(-1),
+          slot: Some(60),
+        })
+      );
+      assert_eq!(
+        np.find_index(values[67], 1000)?.map(|l| {
+          return l.index;
+        }),
+        Some(-1)
+      );
+      assert_eq!(np.find_index(values[202], 100)?, None);
+      // Looking doesn't move the predictor.
+      assert_eq!(np.predict_next()?, values[68]);
+      return Ok(());
+    }
+
+    #[test]
+    fn links_sequences_of_one_context() -> Result<(), Box<dyn Error>> {
+      let values = v8_values(256);
+      let seq_a = values[74..78].to_vec();
+      let link = |seq_b: Vec<f64>| {
+        return NodePredictor::link(NodeJsMajorVersion::V24, seq_a.clone(), seq_b, 1000);
+      };
+
+      // Crossing a refill says where `seq_a` was in its cache, so there's one distance.
+      assert_eq!(
+        link(values[190..194].to_vec())?,
+        Linkage::Linked(vec![Location {
+          index: 112,
+          pool: Some(1),
+          slot: Some(62),
+        }])
+      );
+
+      // Otherwise it's only the same context, at one of several distances.
+      let Linkage::Linked(locations) = link(values[148..151].to_vec())? else {
+        return Err(Box::from("Expected the same context"));
+      };
+      assert!(locations.len() > 1);
+      assert!(locations.iter().any(|location| {
+        return location.index == 70;
+      }));
+
+      let other = v8_values_from(OTHER_STATE, XorShiftVariant::Mantissa53, 3);
+      assert_eq!(link(other)?, Linkage::Unlinked);
+      return Ok(());
+    }
+
+    #[test]
+    fn predicts_a_seeded_run() -> Result<(), Box<dyn Error>> {
+      // From `node --random-seed=42` (v20.20.2), its 1st, 2nd, 64th, 65th and 66th values.
+      let mut np = NodePredictor::from_seed(NodeJsMajorVersion::V20, 42)?;
+      assert_eq!(np.predict_next()?, 0.7939112874678715);
+      assert_eq!(np.predict_next()?, 0.5254990606499601);
+      assert_eq!(np.predict_at(61)?, 0.08156904043271651);
+      assert_eq!(np.predict_at(62)?, 0.4706713645501157);
+      assert_eq!(np.predict_at(63)?, 0.2603201442309373);
+
+      // V8 sign extends negative seeds.
+      let mut np = NodePredictor::from_seed(NodeJsMajorVersion::V20, -7)?;
+      assert_eq!(np.predict_next()?, 0.24093511597397543);
+      assert_eq!(np.predict_next()?, 0.5146862225679596);
+
+      assert!(matches!(
+        NodePredictor::from_seed(NodeJsMajorVersion::V4, 42),
+        Err(InitError::Unseedable)
+      ));
+      // 0 is no seed to V8.
+      assert!(matches!(
+        NodePredictor::from_seed(NodeJsMajorVersion::V20, 0),
+        Err(InitError::ZeroSeed)
+      ));
+      return Ok(());
+    }
+
+    #[test]
+    fn has_no_history_before_the_seed() -> Result<(), Box<dyn Error>> {
+      let mut np = NodePredictor::from_seed(NodeJsMajorVersion::V20, 42)?;
+      assert!(np.history(0)?.is_empty());
+      np.predict_next()?;
+      let is_before_seed = |result: Result<Vec<f64>, Box<dyn Error>>| {
+        return matches!(
+          result.err().as_deref().and_then(|e| {
+            return e.downcast_ref::<InitError>();
+          }),
+          Some(InitError::BeforeSeed)
+        );
+      };
+      assert!(is_before_seed(np.history(1)));
+      // Nor after picking it up from a saved state.
+      let mut restored = NodePredictor::from_state(np.state()?)?;
+      assert!(is_before_seed(restored.history(1)));
+      assert_eq!(restored.predict_next()?, 0.5254990606499601);
+      return Ok(());
+    }
+
+    #[test]
+    fn has_history_again_after_reset() -> Result<(), Box<dyn Error>> {
+      let mut seeded = NodePredictor::from_seed(NodeJsMajorVersion::V20, 42)?;
+      let mut first_pool = vec![];
+      for _ in 0..NodePredictor::MAX_NUM_PREDICTIONS {
+        first_pool.push(seeded.predict_next()?);
+      }
+      // The next cache, solved from scratch, picks up from the seeded run.
+      let mut np = NodePredictor::from_seed(NodeJsMajorVersion::V20, 42)?;
+      np.set_pool_aware(true);
+      np.skip(NodePredictor::MAX_NUM_PREDICTIONS as u64)?;
+      let mut second_pool = vec![];
+      for _ in 0..4 {
+        second_pool.push(np.predict_next()?);
+      }
+
+      seeded.reset(second_pool)?;
+      assert_eq!(seeded.predict_previous()?, first_pool[63]);
+      assert_eq!(seeded.history(3)?, first_pool[60..63]);
+      return Ok(());
+    }
+
+    #[test]
+    fn picks_up_from_a_saved_state() -> Result<(), Box<dyn Error>> {
+      let pool = v8_values(64);
+      let mut np = NodePredictor::new(NodeJsMajorVersion::V24, pool[..4].to_vec());
+      assert_eq!(np.predict_next()?, pool[4]);
+
+      let state = np.state()?;
+      assert_eq!(state.pool_index, 5);
+      assert_eq!(state.v8_version, Some(NodeJsMajorVersion::V24.into()));
+      let mut fork = np.fork();
+      let mut restored = NodePredictor::from_state(state)?;
+      for expected in &pool[5..] {
+        assert_eq!(np.predict_next()?, *expected);
+        assert_eq!(restored.predict_next()?, *expected);
+      }
+      assert!(restored.predict_next().is_err());
+      assert_eq!(restored.predict_previous()?, np.predict_previous()?);
+
+      // The fork has a cache of its own, which is still where it was.
+      assert!(np.predict_next().is_err());
+      assert_eq!(fork.predict_next()?, pool[5]);
+      return Ok(());
+    }
+
+    #[test]
+    fn orders_values_collected_out_of_order() -> Result<(), Box<dyn Error>> {
+      let calls = v8_values(128)[60..].to_vec();
+
+      // Responses to parallel requests, across a refill, and one of the calls went elsewhere.
+      let bag = vec![calls[5], calls[0], calls[4], calls[2], calls[6], calls[3]];
+      let mut unordered = NodePredictor::from_unordered(NodeJsMajorVersion::V24, bag, Some(8))?;
+
+      assert_eq!(unordered.positions, vec![5, 0, 4, 2, 6, 3]);
+      assert_eq!(unordered.order, vec![1, 3, 5, 2, 0, 4]);
+      for expected in &calls[7..12] {
+        assert_eq!(unordered.predictor.predict_next()?, *expected);
+      }
+      return Ok(());
+    }
+
+    #[cfg(feature = "z3")]
+    #[test]
+    fn native_and_z3_solvers_agree() -> Result<(), Box<dyn Error>> {
+      let seq = vec![
+        0.36280726230126614,
+        0.32726837947512855,
+        0.22834780314989023,
+        0.18295517908119385,
+      ];
+
+      let mut native =
+        NodePredictor::with_solver(NodeJsMajorVersion::V22, seq.clone(), SolverBackend::Native);
+      let mut z3 = NodePredictor::with_solver(NodeJsMajorVersion::V22, seq, SolverBackend::Z3);
+
+      for _ in 0..10 {
+        assert_eq!(native.predict_next()?, z3.predict_next()?);
+      }
+      return Ok(());
+    }
   }
 
   mod node_v22 {
@@ -400,4 +771,54 @@ mod tests {
       return Ok(());
     }
   }
+
+  mod node_v4 {
+    use crate::{NodePredictor, V8Variant};
+    use std::error::Error;
+
+    // Generated with V8 4.5's `MathRandom` from `math.js` (MWC1616, multipliers 18030 and 36969).
+    const NODE_V4_VALUES: [f64; 16] = [
+      0.24155808985233307,
+      0.151628524530679,
+      0.8938275438267738,
+      0.5096967301797122,
+      0.944535834249109,
+      0.09367666696198285,
+      0.19678869610652328,
+      0.9212891112547368,
+      0.7308646037708968,
+      0.482910284306854,
+      0.07119427435100079,
+      0.5488470601849258,
+      0.6657961700111628,
+      0.28597266669385135,
+      0.1315006692893803,
+      0.028081287629902363,
+    ];
+
+    #[test]
+    fn correctly_predicts_sequence() -> Result<(), Box<dyn Error>> {
+      let mut np = NodePredictor::new(crate::NodeJsMajorVersion::V4, NODE_V4_VALUES[4..8].to_vec());
+      assert_eq!(np.variant(), V8Variant::Mwc1616);
+
+      let mut predictions = vec![];
+      for _ in 8..NODE_V4_VALUES.len() {
+        predictions.push(np.predict_next()?);
+      }
+
+      assert_eq!(predictions, NODE_V4_VALUES[8..]);
+      assert_eq!(np.history(4)?, NODE_V4_VALUES[..4]);
+      return Ok(());
+    }
+
+    #[test]
+    fn has_no_pool_to_exhaust() -> Result<(), Box<dyn Error>> {
+      let mut np = NodePredictor::new(crate::NodeJsMajorVersion::V4, NODE_V4_VALUES[..3].to_vec());
+      for _ in 0..(2 * NodePredictor::MAX_NUM_PREDICTIONS) {
+        np.predict_next()?;
+      }
+      assert_eq!(np.pool_index()?, 0);
+      return Ok(());
+    }
+  }
 }
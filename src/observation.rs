@@ -0,0 +1,314 @@
+use crate::errors::InitError;
+
+// What we know about one value `Math.random()` returned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Observation {
+  // The exact double.
+  Exact(f64),
+  // `Math.floor(Math.random() * range) + offset` returned `value`, eg. a dice roll or array index.
+  Integer { value: i64, range: u64, offset: i64 },
+  // Somewhere between `low` and `high` (inclusive), eg. a value rounded for display or logging.
+  Interval { low: f64, high: f64 },
+  // The value after going through a `Float32Array` (or `Math.fround`).
+  Float32(f32),
+  // A call we didn't see, eg. one made by code we can't watch. Only moves the generator along.
+  Unknown,
+}
+
+impl From<f64> for Observation {
+  fn from(value: f64) -> Self {
+    return Observation::Exact(value);
+  }
+}
+
+impl Observation {
+  // `Math.floor(Math.random() * range)`, the most common way integers are made. The range has to
+  // be at least 1, and at most 2^53, past which the integers are no finer than the doubles.
+  pub fn integer(value: i64, range: u64) -> Result<Self, InitError> {
+    if !Self::is_valid_range(range) {
+      return Err(InitError::UnsupportedObservation(
+        "an integer's range has to be between 1 and 2^53",
+      ));
+    }
+    return Ok(Observation::Integer {
+      value,
+      range,
+      offset: 0,
+    });
+  }
+
+  // `value.toFixed(digits)`, or any other rounding to `digits` decimal places.
+  pub fn rounded(value: f64, digits: i32) -> Self {
+    let tolerance = 0.5 * 10f64.powi(-digits);
+    return Observation::Interval {
+      low: value - tolerance,
+      high: value + tolerance,
+    };
+  }
+
+  // Static 'helper' method
+  // Observations of calls that weren't all next to each other, by how many calls in they were
+  // made. The calls in between become `Unknown`. Indices only matter relative to each other, the
+  // result starts at the lowest one. A repeated index keeps its last observation.
+  pub fn sparse(indexed: impl IntoIterator<Item = (usize, Observation)>) -> Vec<Observation> {
+    let mut indexed: Vec<(usize, Observation)> = indexed.into_iter().collect();
+    indexed.sort_by_key(|&(index, _)| {
+      return index;
+    });
+    let Some(&(first, _)) = indexed.first() else {
+      return vec![];
+    };
+
+    let mut observations = vec![Observation::Unknown; indexed[indexed.len() - 1].0 - first + 1];
+    for (index, observation) in indexed {
+      observations[index - first] = observation;
+    }
+    return observations;
+  }
+
+  // Static 'helper' method
+  // Fragments of consecutive observations, with `gaps[i]` calls we didn't see between fragment `i`
+  // and the one after it.
+  pub fn joined(fragments: &[Vec<Observation>], gaps: &[usize]) -> Vec<Observation> {
+    let mut observations = vec![];
+    for (i, fragment) in fragments.iter().enumerate() {
+      if i > 0 {
+        let gap = gaps.get(i - 1).copied().unwrap_or_default();
+        observations.extend(std::iter::repeat_n(Observation::Unknown, gap));
+      }
+      observations.extend_from_slice(fragment);
+    }
+    return observations;
+  }
+
+  // Roughly how many bits of the generator's output this gives away, out of the 53 a double has.
+  pub fn information(&self) -> f64 {
+    let to_double = |mantissa: u64| {
+      return mantissa as f64 / (1u64 << 53) as f64;
+    };
+    return match self.bounds(53, to_double) {
+      Some((low, high)) => 53.0 - ((high - low) as f64 + 1.0).log2(),
+      None => 0.0,
+    };
+  }
+
+  pub fn is_exact(&self) -> bool {
+    return matches!(self, Observation::Exact(_));
+  }
+
+  // Whether `Math.random()` returning `value` explains this observation.
+  pub fn matches(&self, value: f64) -> bool {
+    return !self.is_above(value) && !self.is_below(value);
+  }
+
+  // Smallest and largest `bits` bit number that `to_double` turns into a match, if there are any.
+  // Searches with the same floating point arithmetic JavaScript uses, so rounding at the edges of
+  // a range is exactly what the engine would have done.
+  pub(crate) fn bounds(&self, bits: usize, to_double: impl Fn(u64) -> f64) -> Option<(u64, u64)> {
+    let low = Self::first_where(bits, |n| {
+      return !self.is_above(to_double(n));
+    });
+    let high = Self::first_where(bits, |n| {
+      return self.is_below(to_double(n));
+    });
+    if low >= high {
+      return None;
+    }
+    return Some((low, high - 1));
+  }
+
+  // `value` is too small to match.
+  fn is_above(&self, value: f64) -> bool {
+    return match *self {
+      Observation::Exact(exact) => value < exact,
+      // One built without `integer` can still have a range no value fits.
+      Observation::Integer { range, .. } if !Self::is_valid_range(range) => true,
+      Observation::Integer {
+        value: integer,
+        range,
+        offset,
+      } => (value * range as f64).floor() < (integer as i128 - offset as i128) as f64,
+      Observation::Interval { low, .. } => value < low,
+      Observation::Float32(float) => (value as f32) < float,
+      Observation::Unknown => false,
+    };
+  }
+
+  // `value` is too big to match.
+  fn is_below(&self, value: f64) -> bool {
+    return match *self {
+      Observation::Exact(exact) => value > exact,
+      // One built without `integer` can still have a range no value fits.
+      Observation::Integer { range, .. } if !Self::is_valid_range(range) => true,
+      Observation::Integer {
+        value: integer,
+        range,
+        offset,
+      } => (value * range as f64).floor() > (integer as i128 - offset as i128) as f64,
+      Observation::Interval { high, .. } => value > high,
+      Observation::Float32(float) => (value as f32) > float,
+      Observation::Unknown => false,
+    };
+  }
+
+  // Static 'helper' method
+  fn is_valid_range(range: u64) -> bool {
+    return (1..=1u64 << 53).contains(&range);
+  }
+
+  // Static 'helper' method
+  // Binary search for the first `bits` bit number where `predicate` holds (or `2^bits` if it never
+  // does), given it holds for every number after that one too.
+  fn first_where(bits: usize, predicate: impl Fn(u64) -> bool) -> u64 {
+    let (mut low, mut high) = (0u64, 1u64 << bits);
+    while low < high {
+      let middle = low + (high - low) / 2;
+      if predicate(middle) {
+        high = middle;
+      } else {
+        low = middle + 1;
+      }
+    }
+    return low;
+  }
+}
+
+// The values of the observations that were observed exactly.
+pub(crate) fn exact_values(observations: &[Observation]) -> Vec<f64> {
+  return observations
+    .iter()
+    .filter_map(|observation| {
+      return match observation {
+        Observation::Exact(value) => Some(*value),
+        _ => None,
+      };
+    })
+    .collect();
+}
+
+// Whether observations with these `bounds`, on `bits` bit outputs, give away more than enough to
+// pin down a `state_bits` bit state. Past that a second state fitting is vanishingly unlikely,
+// and proving there isn't one can take a solver far longer than finding the first.
+#[cfg(feature = "z3")]
+pub(crate) fn pins_state(bounds: &[(u64, u64)], bits: usize, state_bits: usize) -> bool {
+  let information: f64 = bounds
+    .iter()
+    .map(|&(low, high)| {
+      return bits as f64 - ((high - low) as f64 + 1.0).log2();
+    })
+    .sum();
+  return information >= (state_bits + STATE_MARGIN) as f64;
+}
+
+// Bits beyond the size of the state before it counts as pinned down.
+#[cfg(feature = "z3")]
+const STATE_MARGIN: usize = 16;
+
+#[cfg(test)]
+mod tests {
+  use crate::{Observation, errors::InitError};
+  use std::error::Error;
+
+  #[test]
+  fn integer_bounds_follow_javascript_rounding() -> Result<(), Box<dyn Error>> {
+    let to_double = |n: u64| {
+      return n as f64 / (1u64 << 53) as f64;
+    };
+    let roll = Observation::Integer {
+      value: 4,
+      range: 6,
+      offset: 1,
+    };
+
+    let (low, high) = roll.bounds(53, to_double).unwrap_or_default();
+    assert!(roll.matches(to_double(low)) && roll.matches(to_double(high)));
+    assert!(!roll.matches(to_double(low - 1)) && !roll.matches(to_double(high + 1)));
+    assert_eq!(
+      (
+        (to_double(low) * 6.0).floor(),
+        (to_double(high) * 6.0).floor()
+      ),
+      (3.0, 3.0)
+    );
+
+    assert!(Observation::integer(6, 6)?.bounds(53, to_double).is_none());
+    assert_eq!(
+      Observation::Exact(0.5).bounds(53, to_double),
+      Some((1 << 52, 1 << 52))
+    );
+    return Ok(());
+  }
+
+  #[test]
+  fn float32_matches_what_a_float32_array_stores() {
+    let value = 0.7362624704291061;
+    let float = Observation::Float32(value as f32);
+    assert!(float.matches(value));
+    assert!(float.matches(value as f32 as f64));
+    assert!(!float.matches(value + 1e-7));
+
+    let rounded = Observation::rounded(0.7363, 4);
+    assert!(rounded.matches(value) && !rounded.matches(0.73635001));
+  }
+
+  #[test]
+  fn information_counts_the_bits_given_away() -> Result<(), Box<dyn Error>> {
+    assert_eq!(Observation::Exact(0.25).information(), 53.0);
+    assert_eq!(Observation::integer(700, 1024)?.information(), 10.0);
+    assert_eq!(Observation::Unknown.information(), 0.0);
+    assert!((Observation::rounded(0.7363, 4).information() - 13.3).abs() < 0.1);
+    return Ok(());
+  }
+
+  #[test]
+  fn rejects_integer_ranges_no_value_fits() -> Result<(), Box<dyn Error>> {
+    for range in [0, (1 << 53) + 1, u64::MAX] {
+      assert!(matches!(
+        Observation::integer(0, range),
+        Err(InitError::UnsupportedObservation(_))
+      ));
+    }
+    assert!(Observation::integer(1 << 52, 1 << 53)?.matches(0.5));
+
+    // Built by hand, they match nothing rather than everything.
+    let empty = Observation::Integer {
+      value: 0,
+      range: 0,
+      offset: 0,
+    };
+    assert!(!empty.matches(0.0) && empty.information() == 0.0);
+
+    // A value and offset far apart don't overflow.
+    let far = Observation::Integer {
+      value: i64::MIN,
+      range: 6,
+      offset: i64::MAX,
+    };
+    assert!(!far.matches(0.5));
+    return Ok(());
+  }
+
+  #[test]
+  fn sparse_fills_the_calls_in_between() {
+    let observations = Observation::sparse([(12, 0.02.into()), (5, 0.31.into()), (7, 0.88.into())]);
+    assert_eq!(
+      observations,
+      vec![
+        Observation::Exact(0.31),
+        Observation::Unknown,
+        Observation::Exact(0.88),
+        Observation::Unknown,
+        Observation::Unknown,
+        Observation::Unknown,
+        Observation::Unknown,
+        Observation::Exact(0.02),
+      ]
+    );
+    assert_eq!(
+      Observation::Unknown.bounds(53, |n| {
+        return n as f64;
+      }),
+      Some((0, (1 << 53) - 1))
+    );
+  }
+}
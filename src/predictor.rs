@@ -1,5 +1,249 @@
+use crate::{Location, PredictorState, Verification, errors::UnsupportedError};
 use std::error::Error;
 
+// Only `predict_next` has to be implemented. The rest fail with `UnsupportedError` unless a
+// predictor implements them too, so predictors written against older releases still compile.
 pub trait Predictor {
   fn predict_next(&mut self) -> Result<f64, Box<dyn Error>>;
+
+  // Steps back one value at a time, starting just before the observed sequence.
+  fn predict_previous(&mut self) -> Result<f64, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("predict_previous")));
+  }
+
+  // Moves past the next `n` values, as if `predict_next` had returned them, without stepping
+  // through each one. V8's cache is refilled along the way, as in pool-aware mode.
+  fn skip(&mut self, _n: u64) -> Result<(), Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("skip")));
+  }
+
+  // The value `predict_next` would return after `index` more calls (so 0 is the next one), however
+  // far ahead, and even past V8's cache. Doesn't move the predictor.
+  fn predict_at(&mut self, _index: u64) -> Result<f64, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("predict_at")));
+  }
+
+  // Where `value` turns up, looking up to `max_distance` calls after and before where the predictor
+  // is, or `None` if it doesn't. Eg. to count the calls between the sequence and a value seen later
+  // in a log. Doesn't move the predictor.
+  fn find_index(
+    &mut self,
+    _value: f64,
+    _max_distance: u64,
+  ) -> Result<Option<Location>, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("find_index")));
+  }
+
+  // The `n` values returned before the sequence (or before the last call to `history`/`predict_previous`),
+  // in the order `Math.random()` returned them, so `history(n) ++ sequence` is chronological.
+  fn history(&mut self, n: usize) -> Result<Vec<f64>, Box<dyn Error>> {
+    let mut values = Vec::with_capacity(n);
+    for _ in 0..n {
+      values.push(self.predict_previous()?);
+    }
+    values.reverse();
+    return Ok(values);
+  }
+
+  // Generator states that fit the observations, up to `limit` of them, as `(state_0, state_1)`
+  // right after the sequence. More than one means the sequence is too short to be sure which one
+  // predictions come from.
+  fn candidate_states(&mut self, _limit: usize) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("candidate_states")));
+  }
+
+  // Indices of the observations that robust mode left out as not fitting the state the rest fit,
+  // eg. values from another tab. Always empty unless the predictor is robust.
+  fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("rejected_indices")));
+  }
+
+  // What the solved state returns for each observed call, next to what was observed. Solving
+  // already fails with `InitError::Unverified` when they don't all match, this shows the replay.
+  fn verify_observations(&mut self) -> Result<Verification, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("verify_observations")));
+  }
+
+  // Where the predictor is now, to save and pick up again with `from_state`. Solves first, if it
+  // hasn't yet.
+  fn state(&mut self) -> Result<PredictorState, Box<dyn Error>> {
+    return Err(Box::new(UnsupportedError("state")));
+  }
+}
+
+// So a predictor picked at runtime, eg. by `detect`, can be used anywhere a predictor can.
+impl<P: Predictor + ?Sized> Predictor for Box<P> {
+  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
+    return (**self).predict_next();
+  }
+
+  fn predict_previous(&mut self) -> Result<f64, Box<dyn Error>> {
+    return (**self).predict_previous();
+  }
+
+  fn skip(&mut self, n: u64) -> Result<(), Box<dyn Error>> {
+    return (**self).skip(n);
+  }
+
+  fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn Error>> {
+    return (**self).predict_at(index);
+  }
+
+  fn find_index(
+    &mut self,
+    value: f64,
+    max_distance: u64,
+  ) -> Result<Option<Location>, Box<dyn Error>> {
+    return (**self).find_index(value, max_distance);
+  }
+
+  fn candidate_states(&mut self, limit: usize) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    return (**self).candidate_states(limit);
+  }
+
+  fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn Error>> {
+    return (**self).rejected_indices();
+  }
+
+  fn verify_observations(&mut self) -> Result<Verification, Box<dyn Error>> {
+    return (**self).verify_observations();
+  }
+
+  fn state(&mut self) -> Result<PredictorState, Box<dyn Error>> {
+    return (**self).state();
+  }
+}
+
+// `Predictor`'s methods, but `state`, for a predictor that hands each one to the engine in `$engine`.
+macro_rules! delegate_to_engine {
+  ($engine:ident) => {
+    fn predict_next(&mut self) -> Result<f64, Box<dyn std::error::Error>> {
+      return $crate::Predictor::predict_next(&mut self.$engine);
+    }
+
+    fn predict_previous(&mut self) -> Result<f64, Box<dyn std::error::Error>> {
+      return $crate::Predictor::predict_previous(&mut self.$engine);
+    }
+
+    fn skip(&mut self, n: u64) -> Result<(), Box<dyn std::error::Error>> {
+      return $crate::Predictor::skip(&mut self.$engine, n);
+    }
+
+    fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn std::error::Error>> {
+      return $crate::Predictor::predict_at(&mut self.$engine, index);
+    }
+
+    fn find_index(
+      &mut self,
+      value: f64,
+      max_distance: u64,
+    ) -> Result<Option<$crate::Location>, Box<dyn std::error::Error>> {
+      return $crate::Predictor::find_index(&mut self.$engine, value, max_distance);
+    }
+
+    fn candidate_states(
+      &mut self,
+      limit: usize,
+    ) -> Result<Vec<(u64, u64)>, Box<dyn std::error::Error>> {
+      return $crate::Predictor::candidate_states(&mut self.$engine, limit);
+    }
+
+    fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+      return $crate::Predictor::rejected_indices(&mut self.$engine);
+    }
+
+    fn verify_observations(&mut self) -> Result<$crate::Verification, Box<dyn std::error::Error>> {
+      return $crate::Predictor::verify_observations(&mut self.$engine);
+    }
+  };
+}
+
+// `Predictor`'s methods on the struct itself, so consumers don't have to import the Predictor trait
+// as well as the struct.
+macro_rules! predictor_methods {
+  () => {
+    pub fn predict_next(&mut self) -> Result<f64, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::predict_next(self);
+    }
+
+    pub fn predict_previous(&mut self) -> Result<f64, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::predict_previous(self);
+    }
+
+    pub fn skip(&mut self, n: u64) -> Result<(), Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::skip(self, n);
+    }
+
+    pub fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::predict_at(self, index);
+    }
+
+    pub fn find_index(
+      &mut self,
+      value: f64,
+      max_distance: u64,
+    ) -> Result<Option<$crate::Location>, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::find_index(self, value, max_distance);
+    }
+
+    pub fn history(&mut self, n: usize) -> Result<Vec<f64>, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::history(self, n);
+    }
+
+    pub fn candidate_states(
+      &mut self,
+      limit: usize,
+    ) -> Result<Vec<(u64, u64)>, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::candidate_states(self, limit);
+    }
+
+    pub fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::rejected_indices(self);
+    }
+
+    pub fn verify_observations(
+      &mut self,
+    ) -> Result<$crate::Verification, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::verify_observations(self);
+    }
+
+    pub fn state(&mut self) -> Result<$crate::PredictorState, Box<dyn std::error::Error>> {
+      return <Self as $crate::Predictor>::state(self);
+    }
+  };
+}
+
+pub(crate) use {delegate_to_engine, predictor_methods};
+
+#[cfg(test)]
+mod tests {
+  use super::Predictor;
+  use crate::errors::UnsupportedError;
+  use std::error::Error;
+
+  // A predictor written before the trait had more than `predict_next`.
+  struct Constant;
+
+  impl Predictor for Constant {
+    fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
+      return Ok(0.5);
+    }
+  }
+
+  #[test]
+  fn other_methods_are_unsupported_by_default() -> Result<(), Box<dyn Error>> {
+    let mut predictor = Constant;
+    assert_eq!(predictor.predict_next()?, 0.5);
+    let error = predictor
+      .history(2)
+      .err()
+      .ok_or("stepped back without `predict_previous`")?;
+    assert!(matches!(
+      error.downcast_ref::<UnsupportedError>(),
+      Some(UnsupportedError("predict_previous"))
+    ));
+    assert!(predictor.skip(1).is_err());
+    assert!(predictor.state().is_err());
+    return Ok(());
+  }
 }
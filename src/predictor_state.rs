@@ -0,0 +1,95 @@
+use crate::{Engine, V8Version, errors::StateError};
+
+// Where a solved predictor is, so it can be saved, moved to another process, and picked up again
+// with `from_state` without solving again. With the `serde` feature, it (de)serializes too.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PredictorState {
+  // `PredictorState::FORMAT` of the release that exported it.
+  pub format: u32,
+  pub engine: Engine,
+  // The V8 version Node and Chrome predictors were made for, if known. `None` for Firefox and Safari.
+  pub v8_version: Option<V8Version>,
+  // State the next prediction comes from.
+  pub state_0: u64,
+  pub state_1: u64,
+  // State behind the earliest value returned so far, used by `predict_previous`.
+  pub prev_state_0: u64,
+  pub prev_state_1: u64,
+  // V8 only: how many values of the current cache were handed out, how many were before the
+  // sequence, and the cache index of the earliest value returned so far.
+  pub pool_index: u8,
+  pub pool_offset: u8,
+  pub prev_cache_index: u8,
+  // V8 only: started from a seed, so there's nothing before the earliest value. Missing from
+  // states saved before it was added, which weren't seeded.
+  #[cfg_attr(feature = "serde", serde(default))]
+  pub is_seeded: bool,
+  // V8 before 4.9 only: the multipliers of MWC1616's two halves.
+  pub multipliers: Option<(u32, u32)>,
+}
+
+impl PredictorState {
+  // Bumped whenever a field changes meaning, so a state from an incompatible release is refused.
+  pub const FORMAT: u32 = 1;
+
+  // Static 'helper' method
+  // A state of `engine`, with everything that only some engines have left out.
+  pub(crate) fn new(engine: Engine, (state_0, state_1): (u64, u64), prev: (u64, u64)) -> Self {
+    return PredictorState {
+      format: Self::FORMAT,
+      engine,
+      v8_version: None,
+      state_0,
+      state_1,
+      prev_state_0: prev.0,
+      prev_state_1: prev.1,
+      pool_index: 0,
+      pool_offset: 0,
+      prev_cache_index: 0,
+      is_seeded: false,
+      multipliers: None,
+    };
+  }
+
+  // That a predictor which `runs` the engine can pick up from this state.
+  pub(crate) fn check(&self, runs: impl Fn(Engine) -> bool) -> Result<(), StateError> {
+    if self.format != Self::FORMAT {
+      return Err(StateError::Format(self.format));
+    }
+    if !runs(self.engine) {
+      return Err(StateError::WrongEngine(self.engine));
+    }
+    return Ok(());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::PredictorState;
+  use crate::{Engine, FirefoxPredictor, errors::StateError};
+  #[cfg(feature = "serde")]
+  use crate::{V8Variant, V8Version, v8_predictor::test_values::TEST_STATE};
+
+  #[test]
+  fn refuses_states_of_another_format() {
+    let mut state = PredictorState::new(Engine::FirefoxSafari, (1, 2), (3, 4));
+    state.format += 1;
+    assert!(matches!(
+      FirefoxPredictor::from_state(state),
+      Err(StateError::Format(2))
+    ));
+  }
+
+  #[cfg(feature = "serde")]
+  #[test]
+  fn round_trips_through_json() -> Result<(), Box<dyn std::error::Error>> {
+    let mut state = PredictorState::new(Engine::V8(V8Variant::Mantissa53), TEST_STATE, (1, 2));
+    state.v8_version = Some(V8Version::new(13, 6));
+    state.pool_index = 12;
+
+    let json = serde_json::to_string(&state)?;
+    assert_eq!(serde_json::from_str::<PredictorState>(&json)?, state);
+    return Ok(());
+  }
+}
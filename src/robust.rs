@@ -0,0 +1,145 @@
+use crate::{Observation, errors::InitError};
+
+// Candidates taken from each window, so a window that fits a few states still finds the right one,
+// eg. one for each offset into V8's cache that a short window fits at.
+const CANDIDATES_PER_WINDOW: usize = 64;
+
+// Observations that don't fit the state the most others do, eg. values from another tab, or
+// another generator. `solve` finds states from observations with everything outside a window
+// `Unknown`, and `outputs` turns one of them into the value of every call in the sequence.
+//
+// Whichever subset of the observations one state fits best, its first few with at least
+// `window_bits` of information between them (see `Observation::information`) make a window that
+// finds that state. So every way of picking a window from the observations in order is solved,
+// and the state that most observations fit wins. It's a branch and bound: a window can only beat
+// the best state so far if it skipped fewer observations than the best leaves out, so with few
+// outliers only windows near the start are tried. Windows that the best state already explains
+// would only find it again. That makes it exact, as long as no window fits more than
+// `CANDIDATES_PER_WINDOW` states.
+//
+// Treating every observation as a soft constraint for z3's `Optimize` finds the same subset, but
+// doesn't finish in any reasonable time on these generators, and proving a window doesn't fit does.
+pub(crate) fn outliers<C>(
+  observations: &[Observation],
+  window_bits: f64,
+  solve: impl Fn(&[Observation], usize) -> Result<Vec<C>, InitError>,
+  outputs: impl Fn(&C) -> Vec<f64>,
+) -> Result<Vec<usize>, InitError> {
+  let observed: Vec<usize> = (0..observations.len())
+    .filter(|&i| {
+      return observations[i] != Observation::Unknown;
+    })
+    .collect();
+  // Information in each observed value and all those after it, to know when a window can't fill.
+  let mut remaining = vec![0.0; observed.len() + 1];
+  for k in (0..observed.len()).rev() {
+    remaining[k] = remaining[k + 1] + observations[observed[k]].information();
+  }
+
+  let mut search = Search {
+    observations,
+    observed: &observed,
+    remaining,
+    window_bits,
+    solve,
+    outputs,
+    best: None,
+  };
+  search.extend(&mut vec![], 0, 0.0);
+
+  let (_, fits) = search.best.ok_or(InitError::Unsat)?;
+  return Ok(
+    observed
+      .into_iter()
+      .filter(|&i| {
+        return !fits[i];
+      })
+      .collect(),
+  );
+}
+
+struct Search<'a, S, O> {
+  observations: &'a [Observation],
+  observed: &'a [usize],
+  remaining: Vec<f64>,
+  window_bits: f64,
+  solve: S,
+  outputs: O,
+  // How many observed values the best state so far fits, and whether each observation does.
+  best: Option<(usize, Vec<bool>)>,
+}
+
+impl<S, O> Search<'_, S, O> {
+  // Fills `window` from the `next`th observed value on, with or without each in turn.
+  fn extend<C>(&mut self, window: &mut Vec<usize>, next: usize, bits: f64)
+  where
+    S: Fn(&[Observation], usize) -> Result<Vec<C>, InitError>,
+    O: Fn(&C) -> Vec<f64>,
+  {
+    if bits >= self.window_bits {
+      self.try_window(window);
+      return;
+    }
+    if bits + self.remaining[next] < self.window_bits {
+      return;
+    }
+
+    let i = self.observed[next];
+    window.push(i);
+    self.extend(window, next + 1, bits + self.observations[i].information());
+    window.pop();
+
+    // Whatever a window without it finds, it's left out of, as are the ones skipped before it.
+    let skipped = next + 1 - window.len();
+    let most = self.best.as_ref().map_or(0, |&(most, _)| {
+      return most;
+    });
+    if self.observed.len() - skipped > most {
+      self.extend(window, next + 1, bits);
+    }
+  }
+
+  fn try_window<C>(&mut self, window: &[usize])
+  where
+    S: Fn(&[Observation], usize) -> Result<Vec<C>, InitError>,
+    O: Fn(&C) -> Vec<f64>,
+  {
+    if let Some((_, fits)) = &self.best
+      && window.iter().all(|&i| {
+        return fits[i];
+      })
+    {
+      return;
+    }
+
+    let mut masked = vec![Observation::Unknown; self.observations.len()];
+    for &i in window {
+      masked[i] = self.observations[i];
+    }
+    let Ok(candidates) = (self.solve)(&masked, CANDIDATES_PER_WINDOW) else {
+      return;
+    };
+    for candidate in &candidates {
+      let fits: Vec<bool> = self
+        .observations
+        .iter()
+        .zip((self.outputs)(candidate))
+        .map(|(observation, value)| {
+          return observation.matches(value);
+        })
+        .collect();
+      let num_fits = self
+        .observed
+        .iter()
+        .filter(|&&i| {
+          return fits[i];
+        })
+        .count();
+      if self.best.as_ref().is_none_or(|&(most, _)| {
+        return num_fits > most;
+      }) {
+        self.best = Some((num_fits, fits));
+      }
+    }
+  }
+}
@@ -0,0 +1,12 @@
+use clap::ValueEnum;
+
+/// Which solver is used to recover the internal state of the generator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum SolverBackend {
+  /// Built-in bit-matrix solver. No external dependencies.
+  #[default]
+  Native,
+  /// z3 SMT solver. Requires the `z3` feature.
+  #[cfg(feature = "z3")]
+  Z3,
+}
@@ -0,0 +1,136 @@
+use crate::{Observation, errors::InitError, untangle::combinations};
+use std::error::Error;
+
+// Values that were collected with no call order, eg. from parallel requests, put back in order.
+pub struct Unordered<P> {
+  // A predictor solved from the values in the order found, which predicts what comes after them.
+  pub predictor: P,
+  // Indices of the values, in the order `Math.random()` returned them.
+  pub order: Vec<usize>,
+  // Which call returned each value, counting from the first of them.
+  pub positions: Vec<usize>,
+}
+
+impl<P> Unordered<P> {
+  // Static 'helper' method
+  // `build` solves the values placed at their `positions`, with unknown calls in between.
+  pub(crate) fn build(
+    values: &[f64],
+    positions: Vec<usize>,
+    build: impl Fn(Vec<Observation>) -> Result<P, Box<dyn Error>>,
+  ) -> Result<Self, Box<dyn Error>> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by_key(|&i| {
+      return positions[i];
+    });
+    let predictor = build(Observation::sparse(order.iter().map(|&i| {
+      return (positions[i], Observation::Exact(values[i]));
+    })))?;
+    return Ok(Unordered {
+      predictor,
+      order,
+      positions,
+    });
+  }
+}
+
+// Which call, of `spread` calls in a row, returned each of `values`.
+//
+// Whichever value came first is at call 0, so each value is tried there in turn, with every way of
+// placing `window - 1` of the others at later calls. Those are solved (`solve`, with unknown calls
+// in between and after), and each state that fits is run forward (`outputs`, for `spread` calls)
+// to see if every other value turns up too. Checking a state that way is cheap, so it's only the
+// number of windows that grows with the number of values and the spread.
+pub(crate) fn order<C>(
+  values: &[f64],
+  spread: usize,
+  window: usize,
+  solve: impl Fn(&[Observation]) -> Result<Vec<C>, InitError>,
+  outputs: impl Fn(&C, usize) -> Vec<f64>,
+) -> Result<Vec<usize>, InitError> {
+  if values.len() < window {
+    return Err(InitError::NotEnoughObservations);
+  }
+  for first in 0..values.len() {
+    let others: Vec<usize> = (0..values.len())
+      .filter(|&i| {
+        return i != first;
+      })
+      .collect();
+    for calls in combinations(spread.saturating_sub(1), window - 1) {
+      for picks in permutations(others.len(), window - 1) {
+        let mut observations = vec![Observation::Unknown; spread];
+        observations[0] = Observation::Exact(values[first]);
+        for (&call, &pick) in calls.iter().zip(&picks) {
+          observations[1 + call] = Observation::Exact(values[others[pick]]);
+        }
+
+        let Ok(candidates) = solve(&observations) else {
+          continue;
+        };
+        for candidate in &candidates {
+          if let Some(positions) = placed(values, &outputs(candidate, spread)) {
+            return Ok(positions);
+          }
+        }
+      }
+    }
+  }
+  return Err(InitError::NoOrderFits(spread));
+}
+
+// The call that returned each of `values`, if a stream returning `outputs` returned all of them.
+fn placed(values: &[f64], outputs: &[f64]) -> Option<Vec<usize>> {
+  let mut is_taken = vec![false; outputs.len()];
+  return values
+    .iter()
+    .map(|&value| {
+      let call = (0..outputs.len()).find(|&call| {
+        return !is_taken[call] && outputs[call] == value;
+      })?;
+      is_taken[call] = true;
+      return Some(call);
+    })
+    .collect();
+}
+
+// Every ordered way of picking `k` different numbers of `0..n`.
+fn permutations(n: usize, k: usize) -> impl Iterator<Item = Vec<usize>> {
+  let mut stack: Vec<Vec<usize>> = vec![vec![]];
+  return std::iter::from_fn(move || {
+    while let Some(picks) = stack.pop() {
+      if picks.len() == k {
+        return Some(picks);
+      }
+      for next in (0..n).rev() {
+        if !picks.contains(&next) {
+          let mut longer = picks.clone();
+          longer.push(next);
+          stack.push(longer);
+        }
+      }
+    }
+    return None;
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::permutations;
+
+  #[test]
+  fn permutations_pick_in_order() {
+    assert_eq!(
+      permutations(3, 2).collect::<Vec<_>>(),
+      vec![
+        vec![0, 1],
+        vec![0, 2],
+        vec![1, 0],
+        vec![1, 2],
+        vec![2, 0],
+        vec![2, 1]
+      ]
+    );
+    assert_eq!(permutations(2, 3).count(), 0);
+  }
+}
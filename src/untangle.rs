@@ -0,0 +1,171 @@
+use crate::{Observation, errors::InitError};
+use std::error::Error;
+
+// How many values after the first of a stream its window is looked for in, per stream left to
+// find. Values of one context are rarely further apart than the others' calls in between.
+const SPAN_PER_STREAM: usize = 8;
+
+// A sequence that interleaved values from several contexts, eg. Node `vm` contexts, iframes or
+// workers, which each have a generator of their own, split back into one stream per context.
+pub struct Untangled<P> {
+  // A solved predictor for each stream, which predicts what that context returns next.
+  pub streams: Vec<P>,
+  // Which stream each value of the sequence came from, as an index into `streams`.
+  pub assignment: Vec<usize>,
+}
+
+impl<P> Untangled<P> {
+  // Static 'helper' method
+  // `members` are the indices of the sequence in each stream, `build` solves a stream's values.
+  pub(crate) fn build(
+    sequence: &[f64],
+    members: Vec<Vec<usize>>,
+    build: impl Fn(Vec<f64>) -> Result<P, Box<dyn Error>>,
+  ) -> Result<Self, Box<dyn Error>> {
+    let mut assignment = vec![0; sequence.len()];
+    let mut streams = vec![];
+    for (stream, indices) in members.iter().enumerate() {
+      for &i in indices {
+        assignment[i] = stream;
+      }
+      streams.push(build(
+        indices
+          .iter()
+          .map(|&i| {
+            return sequence[i];
+          })
+          .collect(),
+      )?);
+    }
+    return Ok(Untangled {
+      streams,
+      assignment,
+    });
+  }
+}
+
+// Indices of `sequence` in each of up to `streams` streams, in the order they were found.
+//
+// The first value nobody has claimed yet starts a stream. Every way of picking the next
+// `window - 1` of its values from the ones after it is solved (`solve`, with the window followed by
+// enough unknown calls for the rest of the sequence), until one fits. Each state that does is run
+// forward (`outputs`, for that many calls), and the stream claims each value it predicts, in order.
+// The state that claims the most wins, and the next stream starts from what's left.
+pub(crate) fn untangle<C>(
+  sequence: &[f64],
+  streams: usize,
+  window: usize,
+  solve: impl Fn(&[Observation]) -> Result<Vec<C>, InitError>,
+  outputs: impl Fn(&C, usize) -> Vec<f64>,
+) -> Result<Vec<Vec<usize>>, InitError> {
+  let mut is_claimed = vec![false; sequence.len()];
+  let mut members = vec![];
+
+  for found in 0..streams {
+    let unclaimed: Vec<usize> = (0..sequence.len())
+      .filter(|&i| {
+        return !is_claimed[i];
+      })
+      .collect();
+    if unclaimed.is_empty() {
+      break;
+    }
+    let span = (SPAN_PER_STREAM * (streams - found)).min(unclaimed.len() - 1);
+
+    let mut best: Vec<usize> = vec![];
+    for picks in combinations(span, window - 1) {
+      let mut observations = vec![Observation::Exact(sequence[unclaimed[0]])];
+      for &pick in &picks {
+        observations.push(Observation::Exact(sequence[unclaimed[1 + pick]]));
+      }
+      observations.resize(unclaimed.len(), Observation::Unknown);
+
+      let Ok(candidates) = solve(&observations) else {
+        continue;
+      };
+      for candidate in &candidates {
+        let claimed = claims(sequence, &unclaimed, &outputs(candidate, unclaimed.len()));
+        if claimed.len() > best.len() {
+          best = claimed;
+        }
+      }
+      break;
+    }
+
+    if best.is_empty() {
+      break;
+    }
+    for &i in &best {
+      is_claimed[i] = true;
+    }
+    members.push(best);
+  }
+
+  let unclaimed: Vec<usize> = (0..sequence.len())
+    .filter(|&i| {
+      return !is_claimed[i];
+    })
+    .collect();
+  if !unclaimed.is_empty() {
+    return Err(InitError::Unclaimed(unclaimed));
+  }
+  return Ok(members);
+}
+
+// The `unclaimed` indices a stream returning `values` claims, each value at the first place after
+// the last one it turns up.
+fn claims(sequence: &[f64], unclaimed: &[usize], values: &[f64]) -> Vec<usize> {
+  let mut claimed = vec![];
+  for &i in unclaimed {
+    if claimed.len() < values.len() && sequence[i] == values[claimed.len()] {
+      claimed.push(i);
+    }
+  }
+  return claimed;
+}
+
+// Every way of picking `k` of `0..n`, each in increasing order, starting with the first `k`.
+pub(crate) fn combinations(n: usize, k: usize) -> impl Iterator<Item = Vec<usize>> {
+  let mut next = if k <= n {
+    Some((0..k).collect::<Vec<usize>>())
+  } else {
+    None
+  };
+  return std::iter::from_fn(move || {
+    let current = next.take()?;
+    let mut following = current.clone();
+    // Bump the last pick that still has room, and reset the ones after it.
+    if let Some(i) = (0..k).rev().find(|&i| {
+      return following[i] < n - k + i;
+    }) {
+      following[i] += 1;
+      for j in (i + 1)..k {
+        following[j] = following[j - 1] + 1;
+      }
+      next = Some(following);
+    }
+    return Some(current);
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::combinations;
+
+  #[test]
+  fn combinations_pick_in_order() {
+    let picks: Vec<Vec<usize>> = combinations(4, 2).collect();
+    assert_eq!(
+      picks,
+      vec![
+        vec![0, 1],
+        vec![0, 2],
+        vec![0, 3],
+        vec![1, 2],
+        vec![1, 3],
+        vec![2, 3]
+      ]
+    );
+    assert_eq!(combinations(2, 3).count(), 0);
+  }
+}
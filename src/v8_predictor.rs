@@ -0,0 +1,1416 @@
+use crate::{
+  Engine, Linkage, Location, Observation, Predictor, PredictorState, SolverBackend, Unordered,
+  Untangled, V8Variant,
+  diagnosis::diagnose,
+  errors::*,
+  gf2_solver::{
+    Gf2System, LinearWord, MAX_FREE_VARIABLES, free_assignments, xor_shift_128_plus_linear,
+  },
+  jump,
+  link::link,
+  location::nearest,
+  mwc1616_predictor::Mwc1616Predictor,
+  observation::exact_values,
+  robust::outliers,
+  unordered::order,
+  untangle::untangle,
+  verification::Verification,
+};
+#[cfg(feature = "z3")]
+use crate::{diagnosis::unsat_core, observation::pins_state};
+use std::{
+  error::Error,
+  sync::{Arc, Mutex},
+};
+#[cfg(feature = "z3")]
+use z3::{Config, Context, SatResult, Solver, ast::*};
+
+// V8 (Node and Chrome) does not hand out xorshift128+ output directly. It fills a cache of
+// `CACHE_SIZE` values by stepping forward from where the last refill left off, then hands the
+// cache out from the top index down.
+//
+// Everything here is relative to the state the cache holding the first observed value was filled
+// from, and counts "reads", the values handed out since that cache was filled.
+const CACHE_SIZE: usize = 64;
+
+// How many forward steps past the state the first cache was filled from, the state behind the
+// `read`th value is.
+fn state_index(read: usize) -> usize {
+  let pool = read / CACHE_SIZE;
+  let cache_index = CACHE_SIZE - 1 - read % CACHE_SIZE;
+  return pool * CACHE_SIZE + 1 + cache_index;
+}
+
+// Like `state_index`, but for the value after `reads` values have been handed out. An exhausted
+// cache is only refilled on the next read, so until then we stay at the bottom of the old one.
+fn next_state_index(reads: usize) -> usize {
+  if reads > 0 && reads.is_multiple_of(CACHE_SIZE) {
+    return state_index(reads - 1) - 1;
+  }
+  return state_index(reads);
+}
+
+// How many values of the current cache are used up, after `reads` values have been handed out.
+fn cache_reads(reads: usize) -> usize {
+  if reads > 0 && reads.is_multiple_of(CACHE_SIZE) {
+    return CACHE_SIZE;
+  }
+  return reads % CACHE_SIZE;
+}
+
+// Offsets that keep the whole sequence inside one cache can't be told apart from no offset, so
+// after the first, only those crossing a refill are worth trying.
+fn pool_offsets(len: usize) -> impl Iterator<Item = usize> {
+  return (0..CACHE_SIZE).filter(move |&offset| {
+    return offset == 0 || offset + len > CACHE_SIZE;
+  });
+}
+
+// Upper bound on `state_index` for a sequence of `len` values, whatever pool offset it starts at.
+fn max_state_index(len: usize) -> usize {
+  return len.div_ceil(CACHE_SIZE) * CACHE_SIZE + CACHE_SIZE;
+}
+
+// `state_0` of every state up to `max_state_index(len)`, in terms of the first cache's state.
+fn linear_states(len: usize) -> Vec<LinearWord> {
+  let mut state_0 = LinearWord::state_0();
+  let mut state_1 = LinearWord::state_1();
+  let mut states = vec![state_0];
+  for _ in 0..max_state_index(len) {
+    xor_shift_128_plus_linear(&mut state_0, &mut state_1);
+    states.push(state_0);
+  }
+  return states;
+}
+
+// The `V8Variant`s that run xorshift128+, which differ only in how they turn the `state_0` V8
+// cached into a double. MWC1616 has an engine of its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum XorShiftVariant {
+  Mantissa52,
+  Mantissa53,
+}
+
+impl XorShiftVariant {
+  // Static 'helper' method
+  // `None` for MWC1616.
+  pub(crate) fn of(variant: V8Variant) -> Option<Self> {
+    return match variant {
+      V8Variant::Mantissa52 => Some(XorShiftVariant::Mantissa52),
+      V8Variant::Mantissa53 => Some(XorShiftVariant::Mantissa53),
+      V8Variant::Mwc1616 => None,
+    };
+  }
+
+  // How far `state_0` is shifted right, to get the bits that end up in the double.
+  fn shift(self) -> usize {
+    return match self {
+      XorShiftVariant::Mantissa52 => 12,
+      XorShiftVariant::Mantissa53 => 11,
+    };
+  }
+
+  // Smallest and largest bits of `state_0` (shifted down) that explain `observation`.
+  fn bounds(self, observation: &Observation) -> Option<(u64, u64)> {
+    return observation.bounds(64 - self.shift(), |bits| {
+      return self.to_double(bits << self.shift());
+    });
+  }
+
+  fn to_double(self, state_0: u64) -> f64 {
+    return match self {
+      XorShiftVariant::Mantissa52 => f64::from_bits((state_0 >> 12) | 0x3FF0000000000000) - 1.0,
+      XorShiftVariant::Mantissa53 => (state_0 >> 11) as f64 / (1u64 << 53) as f64,
+    };
+  }
+}
+
+impl From<XorShiftVariant> for V8Variant {
+  fn from(variant: XorShiftVariant) -> Self {
+    return match variant {
+      XorShiftVariant::Mantissa52 => V8Variant::Mantissa52,
+      XorShiftVariant::Mantissa53 => V8Variant::Mantissa53,
+    };
+  }
+}
+
+// Everything Node and Chrome have in common, which is all of it apart from picking a variant.
+// Versions before V8 4.9 use MWC1616 instead, which has no cache, so has no pool to keep track of.
+#[derive(Clone)]
+pub(crate) enum V8Engine {
+  XorShift128Plus(V8Predictor),
+  Mwc1616(Mwc1616Predictor),
+}
+
+impl Predictor for V8Engine {
+  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.predict_next(),
+      V8Engine::Mwc1616(mwc) => mwc.predict_next(),
+    };
+  }
+
+  fn predict_previous(&mut self) -> Result<f64, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.predict_previous(),
+      V8Engine::Mwc1616(mwc) => mwc.predict_previous(),
+    };
+  }
+
+  fn skip(&mut self, n: u64) -> Result<(), Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.skip(n),
+      V8Engine::Mwc1616(mwc) => mwc.skip(n),
+    };
+  }
+
+  fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.predict_at(index),
+      V8Engine::Mwc1616(mwc) => mwc.predict_at(index),
+    };
+  }
+
+  fn find_index(
+    &mut self,
+    value: f64,
+    max_distance: u64,
+  ) -> Result<Option<Location>, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.find_index(value, max_distance),
+      V8Engine::Mwc1616(mwc) => mwc.find_index(value, max_distance),
+    };
+  }
+
+  fn candidate_states(&mut self, limit: usize) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.candidate_states(limit),
+      V8Engine::Mwc1616(mwc) => mwc.candidate_states(limit),
+    };
+  }
+
+  fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.rejected_indices(),
+      V8Engine::Mwc1616(mwc) => mwc.rejected_indices(),
+    };
+  }
+
+  fn verify_observations(&mut self) -> Result<Verification, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.verify_observations(),
+      V8Engine::Mwc1616(mwc) => mwc.verify_observations(),
+    };
+  }
+
+  fn state(&mut self) -> Result<PredictorState, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.state(),
+      V8Engine::Mwc1616(mwc) => mwc.state(),
+    };
+  }
+}
+
+impl V8Engine {
+  pub fn new(observations: Vec<Observation>, variant: V8Variant, solver: SolverBackend) -> Self {
+    return match XorShiftVariant::of(variant) {
+      Some(variant) => V8Engine::XorShift128Plus(V8Predictor::new(observations, variant, solver)),
+      None => V8Engine::Mwc1616(Mwc1616Predictor::new(observations, solver)),
+    };
+  }
+
+  // Static 'helper' method
+  pub(crate) fn from_state(state: &PredictorState) -> Result<Self, StateError> {
+    state.check(|engine| {
+      return matches!(engine, Engine::V8(_));
+    })?;
+    let Engine::V8(variant) = state.engine else {
+      return Err(StateError::WrongEngine(state.engine));
+    };
+    return Ok(match XorShiftVariant::of(variant) {
+      Some(variant) => V8Engine::XorShift128Plus(V8Predictor::from_state(state, variant)),
+      None => V8Engine::Mwc1616(Mwc1616Predictor::from_state(state)),
+    });
+  }
+
+  // Static 'helper' method
+  pub(crate) fn from_seed(seed: i32, variant: V8Variant) -> Result<Self, InitError> {
+    let Some(variant) = XorShiftVariant::of(variant) else {
+      return Err(InitError::Unseedable);
+    };
+    if seed == 0 {
+      return Err(InitError::ZeroSeed);
+    }
+    return Ok(V8Engine::XorShift128Plus(V8Predictor::from_seed(
+      seed, variant,
+    )));
+  }
+
+  pub fn sequence(&self) -> &[f64] {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.sequence(),
+      V8Engine::Mwc1616(mwc) => mwc.sequence(),
+    };
+  }
+
+  pub fn variant(&self) -> V8Variant {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.variant.into(),
+      V8Engine::Mwc1616(_) => V8Variant::Mwc1616,
+    };
+  }
+
+  pub fn set_pool_aware(&mut self, is_pool_aware: bool) {
+    if let V8Engine::XorShift128Plus(v8) = self {
+      v8.set_pool_aware(is_pool_aware);
+    }
+  }
+
+  pub fn set_allow_ambiguous(&mut self, allows_ambiguity: bool) {
+    match self {
+      V8Engine::XorShift128Plus(v8) => v8.allows_ambiguity = allows_ambiguity,
+      V8Engine::Mwc1616(mwc) => mwc.allows_ambiguity = allows_ambiguity,
+    }
+  }
+
+  pub fn set_robust(&mut self, is_robust: bool) {
+    match self {
+      V8Engine::XorShift128Plus(v8) => v8.is_robust = is_robust,
+      V8Engine::Mwc1616(mwc) => mwc.is_robust = is_robust,
+    }
+  }
+
+  // Off for callers that try many sequences, most of which won't fit.
+  pub(crate) fn set_diagnose_unsat(&mut self, diagnoses_unsat: bool) {
+    match self {
+      V8Engine::XorShift128Plus(v8) => v8.diagnoses_unsat = diagnoses_unsat,
+      V8Engine::Mwc1616(mwc) => mwc.diagnoses_unsat = diagnoses_unsat,
+    }
+  }
+
+  pub fn pool_offset(&mut self) -> Result<u8, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.pool_offset(),
+      V8Engine::Mwc1616(_) => Ok(0),
+    };
+  }
+
+  pub fn pool_index(&mut self) -> Result<u8, Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.pool_index(),
+      V8Engine::Mwc1616(_) => Ok(0),
+    };
+  }
+
+  pub(crate) fn solve_symbolic_state(&mut self) -> Result<(), Box<dyn Error>> {
+    return match self {
+      V8Engine::XorShift128Plus(v8) => v8.solve_symbolic_state(),
+      V8Engine::Mwc1616(mwc) => mwc.solve_symbolic_state(),
+    };
+  }
+
+  // Static 'helper' method
+  // Splits `sequence` into the streams of up to `streams` contexts, each solved, and wrapped in
+  // whichever predictor asked.
+  pub(crate) fn untangle<P>(
+    sequence: &[f64],
+    variant: V8Variant,
+    streams: usize,
+    wrap: impl Fn(V8Engine) -> P,
+  ) -> Result<Untangled<P>, Box<dyn Error>> {
+    let members = Engine::V8(variant).untangle(sequence, streams)?;
+    return Untangled::build(sequence, members, |values| {
+      let observations = values.into_iter().map(Observation::from).collect();
+      let mut v8 = V8Engine::new(observations, variant, SolverBackend::default());
+      v8.solve_symbolic_state()?;
+      return Ok(wrap(v8));
+    });
+  }
+
+  // Static 'helper' method
+  // Orders `values` within `max_spread` calls (at least as many as there are values), and solves
+  // for what comes after the last of them.
+  pub(crate) fn from_unordered<P>(
+    values: &[f64],
+    variant: V8Variant,
+    max_spread: Option<usize>,
+    wrap: impl Fn(V8Engine) -> P,
+  ) -> Result<Unordered<P>, Box<dyn Error>> {
+    let spread = max_spread.unwrap_or(values.len()).max(values.len());
+    let positions = match XorShiftVariant::of(variant) {
+      Some(variant) => V8Predictor::order(values, variant, spread)?,
+      None => Mwc1616Predictor::order(values, spread)?,
+    };
+    return Unordered::build(values, positions, |observations| {
+      // The order was found with the native solver, so it's solved the same way.
+      let mut v8 = V8Engine::new(observations, variant, SolverBackend::Native);
+      v8.solve_symbolic_state()?;
+      return Ok(wrap(v8));
+    });
+  }
+
+  // Static 'helper' method
+  pub(crate) fn link(
+    variant: V8Variant,
+    seq_a: Vec<f64>,
+    seq_b: Vec<f64>,
+    max_distance: u64,
+  ) -> Result<Linkage, Box<dyn Error>> {
+    return link(
+      &seq_b,
+      max_distance,
+      |lead_in| {
+        let mut observations = vec![Observation::Unknown; lead_in];
+        observations.extend(seq_a.iter().copied().map(Observation::from));
+        let mut v8 = V8Engine::new(observations, variant, SolverBackend::Native);
+        // The unknown calls could also have crossed a refill before `seq_a`, which is only the
+        // same placement again, so take the first state found, which has no offset.
+        v8.set_allow_ambiguous(lead_in > 0);
+        v8.solve_symbolic_state()?;
+        return Ok(v8);
+      },
+      |a| {
+        return a.placements(seq_a.len());
+      },
+    );
+  }
+
+  // How many places in its cache the `len` values this was solved from could have started at.
+  // Only one if they crossed a refill, which says where, or there's no cache.
+  fn placements(&mut self, len: usize) -> Result<usize, Box<dyn Error>> {
+    let V8Engine::XorShift128Plus(v8) = self else {
+      return Ok(1);
+    };
+    if v8.pool_offset()? > 0 || len >= CACHE_SIZE {
+      return Ok(1);
+    }
+    return Ok(CACHE_SIZE - len + 1);
+  }
+
+  pub fn reset(&mut self, new_sequence: Vec<f64>) -> Result<(), PredictionLimitError> {
+    match self {
+      V8Engine::XorShift128Plus(v8) => return v8.reset(new_sequence),
+      V8Engine::Mwc1616(mwc) => mwc.reset(new_sequence),
+    }
+    return Ok(());
+  }
+}
+
+// xorshift128+, handed out through V8's cache.
+pub(crate) struct V8Predictor {
+  // The exact values observed, `observations` also has any that were only partly observed.
+  sequence: Vec<f64>,
+  observations: Vec<Observation>,
+  is_solved: bool,
+  solver: SolverBackend,
+  variant: XorShiftVariant,
+  conc_state_0: u64,
+  conc_state_1: u64,
+  num_predictions_made: Arc<Mutex<u8>>,
+  // How many values of its cache were handed out before the first value of the sequence.
+  pool_offset: u8,
+  // Keep predicting across cache refills, instead of returning `PredictionLimitError`.
+  is_pool_aware: bool,
+  // State behind the earliest value returned so far, and its index in the cache. Used by `predict_previous`.
+  prev_state_0: u64,
+  prev_state_1: u64,
+  prev_cache_index: u8,
+  // Started from a seed, so nothing came before the earliest value, see `InitError::BeforeSeed`.
+  is_seeded: bool,
+  // Predict from the first state found, even if others fit the observations too.
+  pub(crate) allows_ambiguity: bool,
+  // Work out which observations are to blame when no state fits, see `InitError::Inconsistent`.
+  pub(crate) diagnoses_unsat: bool,
+  // Leave out observations that don't fit the state most of the others do.
+  pub(crate) is_robust: bool,
+  rejected: Option<Vec<usize>>,
+  // What the solved state returns for each observed call, see `Verification`.
+  replayed: Vec<f64>,
+}
+
+impl Predictor for V8Predictor {
+  fn predict_next(&mut self) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?; // if solving fails, error is returned early
+    if self.is_pool_aware {
+      self.refill_exhausted_cache()?;
+    }
+    self.increment_prediction_count()?;
+    let v = self.xor_shift_128_plus_concrete();
+    return Ok(self.variant.to_double(v));
+  }
+
+  fn predict_previous(&mut self) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    if self.is_seeded {
+      return Err(Box::new(InitError::BeforeSeed));
+    }
+    // The cache is handed out from the top index down, so going back in time means stepping
+    // forward, until we reach the top of the cache. Before that, we were at the bottom of the
+    // previous cache, which was filled `2 * cache size - 1` states earlier.
+    if self.prev_cache_index as usize == CACHE_SIZE - 1 {
+      for _ in 0..(2 * CACHE_SIZE - 1) {
+        Self::xor_shift_128_plus_reverse(&mut self.prev_state_0, &mut self.prev_state_1);
+      }
+      self.prev_cache_index = 0;
+    } else {
+      Self::xor_shift_128_plus_forward(&mut self.prev_state_0, &mut self.prev_state_1);
+      self.prev_cache_index += 1;
+    }
+    return Ok(self.variant.to_double(self.prev_state_0));
+  }
+
+  // Values are handed out from the top of each cache down, so within the current cache we step
+  // back, and past it we jump forward to where the cache we end up in was filled.
+  fn skip(&mut self, n: u64) -> Result<(), Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let mut count = self
+      .num_predictions_made
+      .lock()
+      .map_err(PredictionLimitError::from)?;
+    let reads = (*count as u64).checked_add(n).ok_or(JumpOverflowError)?;
+    let cache_size = CACHE_SIZE as u64;
+    // Like `next_state_index`, an exhausted cache is only refilled on the next read.
+    let (refills, cache_reads) = if reads > 0 && reads.is_multiple_of(cache_size) {
+      (reads / cache_size - 1, cache_size)
+    } else {
+      (reads / cache_size, reads % cache_size)
+    };
+    if refills == 0 {
+      for _ in 0..n {
+        Self::xor_shift_128_plus_reverse(&mut self.conc_state_0, &mut self.conc_state_1);
+      }
+    } else {
+      // At least one refill, so at least a cache's worth of reads to take `cache_reads` from.
+      let steps = (refills * cache_size - cache_reads)
+        .checked_add(*count as u64)
+        .ok_or(JumpOverflowError)?;
+      jump::xor_shift_128_plus_jump(&mut self.conc_state_0, &mut self.conc_state_1, steps);
+    }
+    *count = cache_reads as u8;
+    return Ok(());
+  }
+
+  fn predict_at(&mut self, index: u64) -> Result<f64, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let mut ahead = self.clone();
+    ahead.is_pool_aware = true;
+    ahead.skip(index)?;
+    return ahead.predict_next();
+  }
+
+  // Steps the way `predict_next` and `predict_previous` do in pool-aware mode, but on copies of
+  // the state we're at, which the last value returned is a step back in the cache from.
+  fn find_index(
+    &mut self,
+    value: f64,
+    max_distance: u64,
+  ) -> Result<Option<Location>, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let reads = *self
+      .num_predictions_made
+      .lock()
+      .map_err(PredictionLimitError::from)?;
+    let variant = self.variant;
+    let (mut next_0, mut next_1, mut next_reads) =
+      (self.conc_state_0, self.conc_state_1, reads as usize);
+    let (mut prev_0, mut prev_1, mut prev_reads) = (next_0, next_1, reads as usize);
+    let index = nearest(
+      value,
+      max_distance,
+      || {
+        if next_reads == CACHE_SIZE {
+          jump::xor_shift_128_plus_jump(&mut next_0, &mut next_1, 2 * CACHE_SIZE as u64);
+          next_reads = 0;
+        }
+        next_reads += 1;
+        let v = next_0;
+        Self::xor_shift_128_plus_reverse(&mut next_0, &mut next_1);
+        return variant.to_double(v);
+      },
+      || {
+        if prev_reads == 0 {
+          for _ in 0..(2 * CACHE_SIZE - 1) {
+            Self::xor_shift_128_plus_reverse(&mut prev_0, &mut prev_1);
+          }
+          prev_reads = CACHE_SIZE;
+        } else {
+          Self::xor_shift_128_plus_forward(&mut prev_0, &mut prev_1);
+        }
+        prev_reads -= 1;
+        return variant.to_double(prev_0);
+      },
+    );
+    return Ok(index.map(|index| {
+      return Location::in_cache(index, reads, CACHE_SIZE);
+    }));
+  }
+
+  fn candidate_states(&mut self, limit: usize) -> Result<Vec<(u64, u64)>, Box<dyn Error>> {
+    if self.is_restored() {
+      return Ok(vec![(self.conc_state_0, self.conc_state_1)]);
+    }
+    self.reject_outliers()?;
+    let reads = self.observations.len();
+    return Ok(
+      self
+        .candidates(limit)?
+        .into_iter()
+        .map(|(mut state_0, mut state_1, offset)| {
+          let next = next_state_index(offset + reads);
+          jump::xor_shift_128_plus_jump(&mut state_0, &mut state_1, next as u64);
+          return (state_0, state_1);
+        })
+        .collect(),
+    );
+  }
+
+  fn rejected_indices(&mut self) -> Result<Vec<usize>, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(self.rejected.clone().unwrap_or_default());
+  }
+
+  fn verify_observations(&mut self) -> Result<Verification, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(Verification::new(&self.observations, self.replayed.clone()));
+  }
+
+  fn state(&mut self) -> Result<PredictorState, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    let mut state = PredictorState::new(
+      Engine::V8(self.variant.into()),
+      (self.conc_state_0, self.conc_state_1),
+      (self.prev_state_0, self.prev_state_1),
+    );
+    state.pool_index = self.pool_index()?;
+    state.pool_offset = self.pool_offset;
+    state.prev_cache_index = self.prev_cache_index;
+    state.is_seeded = self.is_seeded;
+    return Ok(state);
+  }
+}
+
+// Derived, the count of values handed out would be shared, so a fork would use up our cache too.
+impl Clone for V8Predictor {
+  fn clone(&self) -> Self {
+    let num_predictions_made = match self.num_predictions_made.lock() {
+      Ok(count) => *count,
+      Err(poisoned) => *poisoned.into_inner(),
+    };
+    return V8Predictor {
+      sequence: self.sequence.clone(),
+      observations: self.observations.clone(),
+      num_predictions_made: Arc::new(Mutex::new(num_predictions_made)),
+      rejected: self.rejected.clone(),
+      replayed: self.replayed.clone(),
+      ..*self
+    };
+  }
+}
+
+impl V8Predictor {
+  pub const MAX_NUM_PREDICTIONS: u8 = CACHE_SIZE as u8;
+  // Enough exact values in a row that a window of them pins down one state, see `robust::outliers`.
+  const WINDOW_BITS: f64 = 200.0;
+  const WINDOW_LEN: usize = 4;
+  #[cfg(feature = "z3")]
+  const SS_0_STR: &str = "sym_state_0";
+  #[cfg(feature = "z3")]
+  const SS_1_STR: &str = "sym_state_1";
+
+  pub fn new(
+    observations: Vec<Observation>,
+    variant: XorShiftVariant,
+    solver: SolverBackend,
+  ) -> Self {
+    return V8Predictor {
+      sequence: exact_values(&observations),
+      observations,
+      is_solved: false,
+      solver,
+      variant,
+      conc_state_0: 0,
+      conc_state_1: 0,
+      num_predictions_made: Arc::new(Mutex::new(0)),
+      pool_offset: 0,
+      is_pool_aware: false,
+      prev_state_0: 0,
+      prev_state_1: 0,
+      prev_cache_index: 0,
+      is_seeded: false,
+      allows_ambiguity: false,
+      diagnoses_unsat: true,
+      is_robust: false,
+      rejected: None,
+      replayed: vec![],
+    };
+  }
+
+  // Static 'helper' method
+  // Picks up where `state` left off, which has been checked to be one of `variant`.
+  fn from_state(state: &PredictorState, variant: XorShiftVariant) -> Self {
+    let mut predictor = Self::new(vec![], variant, SolverBackend::Native);
+    (predictor.conc_state_0, predictor.conc_state_1) = (state.state_0, state.state_1);
+    (predictor.prev_state_0, predictor.prev_state_1) = (state.prev_state_0, state.prev_state_1);
+    predictor.num_predictions_made = Arc::new(Mutex::new(state.pool_index));
+    predictor.pool_offset = state.pool_offset;
+    predictor.prev_cache_index = state.prev_cache_index;
+    predictor.is_seeded = state.is_seeded;
+    predictor.is_solved = true;
+    return predictor;
+  }
+
+  // At the first call of a context V8 seeded from `--random-seed`, which is the top of the first
+  // cache filled from the seeded state. Nothing comes before it, so there's no history.
+  fn from_seed(seed: i32, variant: XorShiftVariant) -> Self {
+    let mut predictor = Self::new(vec![], variant, SolverBackend::Native);
+    let (mut state_0, mut state_1) = Self::seed_state(seed);
+    jump::xor_shift_128_plus_jump(&mut state_0, &mut state_1, CACHE_SIZE as u64);
+    (predictor.conc_state_0, predictor.conc_state_1) = (state_0, state_1);
+    (predictor.prev_state_0, predictor.prev_state_1) = (state_0, state_1);
+    predictor.prev_cache_index = (CACHE_SIZE - 1) as u8;
+    predictor.is_seeded = true;
+    predictor.is_solved = true;
+    return predictor;
+  }
+
+  // Static 'helper' method
+  // V8 hashes the seed, and its complement, with MurmurHash3's 64-bit finalizer. The flag is an
+  // `int`, which is sign extended to 64 bits first.
+  fn seed_state(seed: i32) -> (u64, u64) {
+    let murmur_hash_3 = |mut h: u64| {
+      h ^= h >> 33;
+      h = h.wrapping_mul(0xFF51AFD7ED558CCD);
+      h ^= h >> 33;
+      h = h.wrapping_mul(0xC4CEB9FE1A85EC53);
+      h ^= h >> 33;
+      return h;
+    };
+    let seed = seed as i64 as u64;
+    return (murmur_hash_3(seed), murmur_hash_3(!seed));
+  }
+
+  pub fn sequence(&self) -> &[f64] {
+    return &self.sequence;
+  }
+
+  // Picked up from a `PredictorState`, so there's no sequence, only the state it was given.
+  fn is_restored(&self) -> bool {
+    return self.is_solved && self.observations.is_empty();
+  }
+
+  pub fn set_pool_aware(&mut self, is_pool_aware: bool) {
+    self.is_pool_aware = is_pool_aware;
+  }
+
+  pub fn pool_offset(&mut self) -> Result<u8, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(self.pool_offset);
+  }
+
+  pub fn pool_index(&mut self) -> Result<u8, Box<dyn Error>> {
+    self.solve_symbolic_state()?;
+    return Ok(
+      *self
+        .num_predictions_made
+        .lock()
+        .map_err(PredictionLimitError::from)?,
+    );
+  }
+
+  // Only resets once the cache is exhausted, until then predictions are still accurate.
+  pub fn reset(&mut self, new_sequence: Vec<f64>) -> Result<(), PredictionLimitError> {
+    let mut c = self.num_predictions_made.lock()?;
+    if *c < Self::MAX_NUM_PREDICTIONS {
+      return Ok(());
+    }
+    *c = new_sequence.len() as u8;
+    self.is_solved = false;
+    // Solved from the new sequence, so there's history before it again.
+    self.is_seeded = false;
+    self.rejected = None;
+    self.observations = new_sequence
+      .iter()
+      .copied()
+      .map(Observation::from)
+      .collect();
+    self.sequence = new_sequence;
+    return Ok(());
+  }
+
+  fn xor_shift_128_plus_concrete(&mut self) -> u64 {
+    let result = self.conc_state_0;
+    Self::xor_shift_128_plus_reverse(&mut self.conc_state_0, &mut self.conc_state_1);
+    return result;
+  }
+
+  // Static 'helper' method
+  fn xor_shift_128_plus_reverse(state_0: &mut u64, state_1: &mut u64) {
+    let t1 = *state_0;
+    let mut t0 = *state_1 ^ (*state_0 >> 26);
+    t0 ^= *state_0;
+    t0 ^= (t0 >> 17) ^ (t0 >> 34) ^ (t0 >> 51);
+    t0 ^= (t0 << 23) ^ (t0 << 46);
+    *state_0 = t0;
+    *state_1 = t1;
+  }
+
+  // Static 'helper' method
+  pub fn xor_shift_128_plus_forward(state_0: &mut u64, state_1: &mut u64) {
+    let mut s1 = *state_0;
+    let s0 = *state_1;
+    s1 ^= s1 << 23;
+    s1 ^= s1 >> 17;
+    s1 ^= s0;
+    s1 ^= s0 >> 26;
+    *state_0 = s0;
+    *state_1 = s1;
+  }
+
+  // If our count is below the max, we can increment, otherwise error.
+  fn increment_prediction_count(&self) -> Result<(), PredictionLimitError> {
+    let mut c = self.num_predictions_made.lock()?;
+    if *c >= Self::MAX_NUM_PREDICTIONS {
+      return Err(PredictionLimitError);
+    }
+    *c += 1;
+    return Ok(());
+  }
+
+  // V8 refills an exhausted cache from where the generator left off, then hands it out top index
+  // first. So the next value is `2 * cache size` states ahead of the state we are at.
+  fn refill_exhausted_cache(&mut self) -> Result<(), PredictionLimitError> {
+    let mut c = self.num_predictions_made.lock()?;
+    if *c < Self::MAX_NUM_PREDICTIONS {
+      return Ok(());
+    }
+    jump::xor_shift_128_plus_jump(
+      &mut self.conc_state_0,
+      &mut self.conc_state_1,
+      2 * CACHE_SIZE as u64,
+    );
+    *c = 0;
+    return Ok(());
+  }
+
+  pub(crate) fn solve_symbolic_state(&mut self) -> Result<(), Box<dyn Error>> {
+    if self.is_solved {
+      return Ok(());
+    }
+    self.reject_outliers()?;
+
+    // A second candidate is all it takes to know predictions could come from the wrong state.
+    let limit = if self.allows_ambiguity { 1 } else { 2 };
+    let candidates = self.candidates(limit)?;
+    if candidates.len() > 1 {
+      return Err(Box::new(InitError::Ambiguous));
+    }
+    let offset;
+    (self.conc_state_0, self.conc_state_1, offset) = candidates[0];
+    self.replayed = Self::outputs(self.variant, candidates[0], self.observations.len());
+    Verification::new(&self.observations, self.replayed.clone()).check()?;
+    self.pool_offset = offset as u8;
+
+    let reads = offset + self.observations.len();
+
+    // The first value of the sequence, and where it was in its cache.
+    self.prev_state_0 = self.conc_state_0;
+    self.prev_state_1 = self.conc_state_1;
+    let first = state_index(offset);
+    jump::xor_shift_128_plus_jump(&mut self.prev_state_0, &mut self.prev_state_1, first as u64);
+    self.prev_cache_index = (CACHE_SIZE - 1 - offset) as u8;
+
+    // The value after the last one of the sequence, which may be in a later cache.
+    let next = next_state_index(reads);
+    jump::xor_shift_128_plus_jump(&mut self.conc_state_0, &mut self.conc_state_1, next as u64);
+    *self
+      .num_predictions_made
+      .lock()
+      .map_err(PredictionLimitError::from)? = cache_reads(reads) as u8;
+
+    self.is_solved = true;
+    return Ok(());
+  }
+
+  // Sets the observations that don't fit aside as unknown calls, once, if robust.
+  fn reject_outliers(&mut self) -> Result<(), InitError> {
+    if !self.is_robust || self.rejected.is_some() {
+      return Ok(());
+    }
+    // The native solver rules a window out far faster than z3, and takes partial observations too.
+    let rejected = outliers(
+      &self.observations,
+      Self::WINDOW_BITS,
+      |observations, limit| {
+        let mut predictor = Self::new(observations.to_vec(), self.variant, SolverBackend::Native);
+        predictor.diagnoses_unsat = false;
+        return predictor.candidates(limit);
+      },
+      |&candidate| {
+        return Self::outputs(self.variant, candidate, self.observations.len());
+      },
+    )?;
+    for &i in &rejected {
+      self.observations[i] = Observation::Unknown;
+    }
+    self.sequence = exact_values(&self.observations);
+    self.rejected = Some(rejected);
+    return Ok(());
+  }
+
+  // Static 'helper' method
+  // What a candidate from `candidates` returns for its first `len` calls.
+  fn outputs(
+    variant: XorShiftVariant,
+    (mut state_0, mut state_1, offset): (u64, u64, usize),
+    len: usize,
+  ) -> Vec<f64> {
+    let mut states = vec![state_0];
+    for _ in 0..max_state_index(len) {
+      Self::xor_shift_128_plus_forward(&mut state_0, &mut state_1);
+      states.push(state_0);
+    }
+    return (0..len)
+      .map(|i| {
+        return variant.to_double(states[state_index(offset + i)]);
+      })
+      .collect();
+  }
+
+  // Static 'helper' method
+  // Indices of `sequence` in each of up to `streams` interleaved streams, see `untangle::untangle`.
+  // A stream's window is solved at every offset into its cache, since where it crosses a refill
+  // only shows further on.
+  pub(crate) fn untangle(
+    sequence: &[f64],
+    variant: XorShiftVariant,
+    streams: usize,
+  ) -> Result<Vec<Vec<usize>>, InitError> {
+    return untangle(
+      sequence,
+      streams,
+      Self::WINDOW_LEN,
+      |observations| {
+        let mut predictor = Self::new(observations.to_vec(), variant, SolverBackend::Native);
+        predictor.diagnoses_unsat = false;
+        return predictor.candidates(CACHE_SIZE);
+      },
+      |&candidate, len| {
+        return Self::outputs(variant, candidate, len);
+      },
+    );
+  }
+
+  // Static 'helper' method
+  // Which call, of `spread` in a row, returned each of `values`, see `unordered::order`. Three
+  // values pin the state, and since each is a linear function of it, a placement of them is one
+  // GF(2) elimination per cache offset. Most placements that don't fit fail there, before any state
+  // is run forward.
+  pub(crate) fn order(
+    values: &[f64],
+    variant: XorShiftVariant,
+    spread: usize,
+  ) -> Result<Vec<usize>, InitError> {
+    return order(
+      values,
+      spread,
+      Self::WINDOW_LEN - 1,
+      |observations| {
+        let mut predictor = Self::new(observations.to_vec(), variant, SolverBackend::Native);
+        predictor.diagnoses_unsat = false;
+        return predictor.candidates(CACHE_SIZE);
+      },
+      |&candidate, len| {
+        return Self::outputs(variant, candidate, len);
+      },
+    );
+  }
+
+  // States the cache of the first value was filled from, that fit the observations, and how far
+  // into that cache the first value was. Up to `limit` of them.
+  fn candidates(&self, limit: usize) -> Result<Vec<(u64, u64, usize)>, InitError> {
+    let candidates = match self.solver {
+      SolverBackend::Native => self.native_candidates(limit),
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3 => self.z3_candidates(limit),
+    };
+    if self.diagnoses_unsat && matches!(candidates, Err(InitError::Unsat)) {
+      return Err(self.diagnose());
+    }
+    return candidates;
+  }
+
+  // Which observations are to blame for no state fitting.
+  fn diagnose(&self) -> InitError {
+    let core = match self.solver {
+      SolverBackend::Native => None,
+      #[cfg(feature = "z3")]
+      SolverBackend::Z3 => self.z3_unsat_core(),
+    };
+    let is_possible = |observation: &Observation| {
+      return self.variant.bounds(observation).is_some();
+    };
+    return diagnose(&self.observations, core, is_possible, |observations| {
+      let mut predictor = Self::new(observations.to_vec(), self.variant, self.solver);
+      predictor.diagnoses_unsat = false;
+      return Ok(predictor.candidates(2)?.len());
+    });
+  }
+
+  // Other code may have used up part of the cache before the sequence, so try every offset into
+  // it. A sequence that never crosses a refill fits any of them, and takes the first (no offset).
+  fn native_candidates(&self, limit: usize) -> Result<Vec<(u64, u64, usize)>, InitError> {
+    let bounds = self.observation_bounds()?;
+    let states = linear_states(bounds.len());
+    let shift = self.variant.shift();
+    let mut candidates = vec![];
+    let mut is_underdetermined = false;
+
+    'offsets: for offset in pool_offsets(bounds.len()) {
+      let mut system = Gf2System::new();
+
+      // Only the leading bits both bounds share are certain, candidates are checked against the
+      // whole range afterwards.
+      for (i, &(low, high)) in bounds.iter().enumerate() {
+        let state_0 = &states[state_index(offset + i)];
+        let varying = (64 - (low ^ high).leading_zeros()) as usize;
+        if !system.add_word(&state_0.lshr(shift), varying..(64 - shift), low) {
+          continue 'offsets;
+        }
+      }
+
+      // Too many states fit to pick one, any we did pick would most likely be wrong.
+      let free = system.free_variables();
+      if free.len() > MAX_FREE_VARIABLES {
+        is_underdetermined = true;
+        continue;
+      }
+      for free_bits in free_assignments(&free) {
+        let unknowns = system.solve_with(free_bits);
+        let fits = bounds.iter().enumerate().all(|(i, &(low, high))| {
+          let bits = states[state_index(offset + i)].eval(unknowns) >> shift;
+          return low <= bits && bits <= high;
+        });
+        if fits {
+          candidates.push((unknowns as u64, (unknowns >> 64) as u64, offset));
+          if candidates.len() >= limit {
+            return Ok(candidates);
+          }
+        }
+      }
+    }
+
+    // Some offset had more states than we could check, so there may be more candidates than these.
+    if is_underdetermined {
+      return Err(InitError::NotEnoughObservations);
+    }
+    if candidates.is_empty() {
+      return Err(InitError::Unsat);
+    }
+    return Ok(candidates);
+  }
+
+  // An observation no double of this variant explains is unsatisfiable on its own.
+  fn observation_bounds(&self) -> Result<Vec<(u64, u64)>, InitError> {
+    return self
+      .observations
+      .iter()
+      .map(|observation| {
+        return self.variant.bounds(observation).ok_or(InitError::Unsat);
+      })
+      .collect();
+  }
+
+  #[cfg(feature = "z3")]
+  fn z3_candidates(&self, limit: usize) -> Result<Vec<(u64, u64, usize)>, InitError> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+
+    let bounds = self.observation_bounds()?;
+    let limit = if pins_state(&bounds, 64 - self.variant.shift(), 128) {
+      1
+    } else {
+      limit
+    };
+    let (base_state_0, base_state_1, states) = Self::z3_states(&context, bounds.len());
+
+    // Same search over cache offsets as `native_candidates`.
+    let mut candidates = vec![];
+    for offset in pool_offsets(bounds.len()) {
+      let (first, constraints) = self.offset_constraints(&context, &states, &bounds, offset);
+      solver.push();
+      for constraint in &constraints {
+        solver.assert(constraint);
+      }
+
+      while candidates.len() < limit && solver.check() == SatResult::Sat {
+        let model = solver.get_model().ok_or(InitError::MissingModel)?;
+
+        let mut state_0 = model
+          .eval(&base_state_0, true)
+          .ok_or(InitError::EvalFailed(Self::SS_0_STR))?
+          .as_u64()
+          .ok_or(InitError::ConvertFailed(Self::SS_0_STR))?;
+
+        let mut state_1 = model
+          .eval(&base_state_1, true)
+          .ok_or(InitError::EvalFailed(Self::SS_1_STR))?
+          .as_u64()
+          .ok_or(InitError::ConvertFailed(Self::SS_1_STR))?;
+
+        // Rule this state out, so the next check finds another one if there is one.
+        solver.assert(&Bool::or(
+          &context,
+          &[
+            &base_state_0._eq(&BV::from_u64(&context, state_0, 64)).not(),
+            &base_state_1._eq(&BV::from_u64(&context, state_1, 64)).not(),
+          ],
+        ));
+
+        // Step back to the state the cache was filled from, which is what the native solver gives us.
+        for _ in 0..first {
+          Self::xor_shift_128_plus_reverse(&mut state_0, &mut state_1);
+        }
+        candidates.push((state_0, state_1, offset));
+      }
+      solver.pop(1);
+
+      if candidates.len() >= limit {
+        break;
+      }
+    }
+
+    if candidates.is_empty() {
+      return Err(InitError::Unsat);
+    }
+    return Ok(candidates);
+  }
+
+  // The smallest set of observations that z3 found can't all be right, at any offset.
+  #[cfg(feature = "z3")]
+  fn z3_unsat_core(&self) -> Option<Vec<usize>> {
+    let config = Config::new();
+    let context = Context::new(&config);
+    let solver = Solver::new(&context);
+
+    let bounds = self.observation_bounds().ok()?;
+    let (_, _, states) = Self::z3_states(&context, bounds.len());
+    let mut smallest: Option<Vec<usize>> = None;
+    for offset in pool_offsets(bounds.len()) {
+      let (_, constraints) = self.offset_constraints(&context, &states, &bounds, offset);
+      let core = unsat_core(&context, &solver, &constraints)?;
+      if smallest.as_ref().is_none_or(|smallest| {
+        return core.len() < smallest.len();
+      }) {
+        smallest = Some(core);
+      }
+    }
+    return smallest;
+  }
+
+  // Static 'helper' method
+  // Unknown base state, and the `state_0` of every state after it a sequence of `len` can read.
+  // Long xorshift chains are slow for z3, so the base is the earliest state observed, rather than
+  // the one the cache was filled from.
+  #[cfg(feature = "z3")]
+  fn z3_states(context: &Context, len: usize) -> (BV<'_>, BV<'_>, Vec<BV<'_>>) {
+    let base_state_0 = BV::new_const(context, Self::SS_0_STR, 64);
+    let base_state_1 = BV::new_const(context, Self::SS_1_STR, 64);
+
+    let mut sym_state_0 = base_state_0.clone();
+    let mut sym_state_1 = base_state_1.clone();
+    let mut states = vec![sym_state_0.clone()];
+    for _ in 0..max_state_index(len) {
+      Self::xor_shift_128_plus_symbolic(context, &mut sym_state_0, &mut sym_state_1);
+      states.push(sym_state_0.clone());
+    }
+    return (base_state_0, base_state_1, states);
+  }
+
+  // A constraint for each observation, if the first value was `offset` into its cache. Also how
+  // many states after the one it was filled from the base state of `states` is.
+  #[cfg(feature = "z3")]
+  fn offset_constraints<'a>(
+    &self,
+    context: &'a Context,
+    states: &[BV<'a>],
+    bounds: &[(u64, u64)],
+    offset: usize,
+  ) -> (usize, Vec<Bool<'a>>) {
+    let indices: Vec<usize> = (0..bounds.len())
+      .map(|i| {
+        return state_index(offset + i);
+      })
+      .collect();
+    let first = indices.iter().min().copied().unwrap_or(0);
+    let constraints = bounds
+      .iter()
+      .zip(&indices)
+      .map(|(&observed, &index)| {
+        return Self::mantissa_constraint(observed, self.variant, context, &states[index - first]);
+      })
+      .collect();
+    return (first, constraints);
+  }
+
+  // Static 'helper' method
+  #[cfg(feature = "z3")]
+  fn xor_shift_128_plus_symbolic<'a>(
+    context: &'a Context,
+    state_0: &mut BV<'a>,
+    state_1: &mut BV<'a>,
+  ) {
+    let state_0_shifted_left = state_0.bvshl(&BV::from_u64(context, 23, 64));
+    let mut s1 = &*state_0 ^ state_0_shifted_left;
+    let s1_shifted_right = s1.bvlshr(&BV::from_u64(context, 17, 64));
+
+    s1 ^= s1_shifted_right;
+    s1 ^= state_1.clone();
+    s1 ^= state_1.bvlshr(&BV::from_u64(context, 26, 64));
+
+    std::mem::swap(state_0, state_1);
+    *state_1 = s1;
+  }
+
+  // Static 'helper' method
+  #[cfg(feature = "z3")]
+  fn mantissa_constraint<'a>(
+    (low, high): (u64, u64),
+    variant: XorShiftVariant,
+    context: &'a Context,
+    state_0: &BV<'a>,
+  ) -> Bool<'a> {
+    let mantissa = state_0.bvlshr(&BV::from_u64(context, variant.shift() as u64, 64));
+    if low == high {
+      return mantissa._eq(&BV::from_u64(context, low, 64));
+    }
+    return Bool::and(
+      context,
+      &[
+        &mantissa.bvuge(&BV::from_u64(context, low, 64)),
+        &mantissa.bvule(&BV::from_u64(context, high, 64)),
+      ],
+    );
+  }
+}
+
+// Everything `NodePredictor` and `ChromePredictor` have, for `$name`, which keeps its `V8Engine` in
+// `v8`, and the V8 version it was made for in `version`.
+macro_rules! v8_predictor {
+  ($name:ident) => {
+    impl $crate::Predictor for $name {
+      $crate::predictor::delegate_to_engine!(v8);
+
+      fn state(&mut self) -> Result<$crate::PredictorState, Box<dyn std::error::Error>> {
+        let mut state = $crate::Predictor::state(&mut self.v8)?;
+        state.v8_version = self.version;
+        return Ok(state);
+      }
+    }
+
+    impl $name {
+      pub const MAX_NUM_PREDICTIONS: u8 = $crate::v8_predictor::V8Predictor::MAX_NUM_PREDICTIONS;
+
+      // Takes the runtime's major version, or the `V8Version` from `Runtime::v8_version`.
+      pub fn new(version: impl Into<$crate::V8Version>, seq: Vec<f64>) -> Self {
+        return Self::with_solver(version, seq, $crate::SolverBackend::default());
+      }
+
+      pub fn with_solver(
+        version: impl Into<$crate::V8Version>,
+        seq: Vec<f64>,
+        solver: $crate::SolverBackend,
+      ) -> Self {
+        let observations = seq.into_iter().map($crate::Observation::from).collect();
+        return Self::from_observations(version, observations, solver);
+      }
+
+      // For when only part of each value leaked, eg. `Observation::integer(roll, 6)` for a dice
+      // roll. Partial observations say less each, so it takes more of them.
+      pub fn from_observations(
+        version: impl Into<$crate::V8Version>,
+        observations: Vec<$crate::Observation>,
+        solver: $crate::SolverBackend,
+      ) -> Self {
+        let version = version.into();
+        return $name {
+          v8: $crate::v8_predictor::V8Engine::new(observations, version.variant(), solver),
+          version: Some(version),
+        };
+      }
+
+      // Picks up where a Node or Chrome predictor's `state` left off (any V8 engine, so eg. one
+      // `detect` found too), without solving again.
+      pub fn from_state(state: $crate::PredictorState) -> Result<Self, $crate::errors::StateError> {
+        return Ok($name {
+          v8: $crate::v8_predictor::V8Engine::from_state(&state)?,
+          version: state.v8_version,
+        });
+      }
+
+      // Predicts a run started with a `--random-seed` of `seed`, from its first call.
+      pub fn from_seed(
+        version: impl Into<$crate::V8Version>,
+        seed: i32,
+      ) -> Result<Self, $crate::errors::InitError> {
+        let version = version.into();
+        return Ok($name {
+          v8: $crate::v8_predictor::V8Engine::from_seed(seed, version.variant())?,
+          version: Some(version),
+        });
+      }
+
+      // A copy to try what-ifs with, which predicts on its own from where this one is, and has a
+      // cache of its own to use up.
+      pub fn fork(&self) -> Self {
+        return self.clone();
+      }
+
+      // Splits a sequence logged from several contexts (eg. iframes, Node `vm` contexts or workers,
+      // which each have a generator of their own) into up to `streams` of them, with a solved
+      // predictor for each. Each context needs 4 values in its stream, or 3 before V8 4.9.
+      pub fn untangle(
+        version: impl Into<$crate::V8Version>,
+        sequence: Vec<f64>,
+        streams: usize,
+      ) -> Result<$crate::Untangled<Self>, Box<dyn std::error::Error>> {
+        let version = version.into();
+        return $crate::v8_predictor::V8Engine::untangle(
+          &sequence,
+          version.variant(),
+          streams,
+          |v8| {
+            return $name {
+              v8,
+              version: Some(version),
+            };
+          },
+        );
+      }
+
+      // For values collected with no call order, eg. from parallel requests. Finds which call
+      // returned each, all within `max_spread` calls in a row (by default as many as there are
+      // values, so none were missed), and a predictor for what comes after the last of them.
+      // Takes 3 values.
+      pub fn from_unordered(
+        version: impl Into<$crate::V8Version>,
+        values: Vec<f64>,
+        max_spread: Option<usize>,
+      ) -> Result<$crate::Unordered<Self>, Box<dyn std::error::Error>> {
+        let version = version.into();
+        return $crate::v8_predictor::V8Engine::from_unordered(
+          &values,
+          version.variant(),
+          max_spread,
+          |v8| {
+            return $name {
+              v8,
+              version: Some(version),
+            };
+          },
+        );
+      }
+
+      // Whether `seq_b` came from the same context as `seq_a`, eg. the same process or tab, and
+      // how many calls apart, looking up to `max_distance` calls either way. Unless `seq_a`
+      // crossed a cache refill, or `seq_b` did, there may be more than one distance, see
+      // `Linkage`.
+      pub fn link(
+        version: impl Into<$crate::V8Version>,
+        seq_a: Vec<f64>,
+        seq_b: Vec<f64>,
+        max_distance: u64,
+      ) -> Result<$crate::Linkage, Box<dyn std::error::Error>> {
+        let version = version.into();
+        return $crate::v8_predictor::V8Engine::link(version.variant(), seq_a, seq_b, max_distance);
+      }
+
+      #[allow(dead_code)]
+      pub fn sequence(&self) -> &[f64] {
+        return self.v8.sequence();
+      }
+
+      // Which generator, and conversion to a double, the version given to `new` uses.
+      pub fn variant(&self) -> $crate::V8Variant {
+        return self.v8.variant();
+      }
+
+      $crate::predictor::predictor_methods!();
+
+      // Without this, solving fails with `InitError::Ambiguous` when more than one state fits.
+      pub fn set_allow_ambiguous(&mut self, allows_ambiguity: bool) {
+        self.v8.set_allow_ambiguous(allows_ambiguity);
+      }
+
+      // Predict from the largest part of the sequence one state fits, leaving out values that
+      // came from somewhere else. `rejected_indices` says which ones were left out.
+      pub fn set_robust(&mut self, is_robust: bool) {
+        self.v8.set_robust(is_robust);
+      }
+
+      // When enabled, predictions continue past the end of the cache pool, the same way V8
+      // refills it. MWC1616 (before V8 4.9) has no cache, so always keeps predicting.
+      pub fn set_pool_aware(&mut self, is_pool_aware: bool) {
+        self.v8.set_pool_aware(is_pool_aware);
+      }
+
+      // How many values of its cache were handed out before the first value of the sequence.
+      pub fn pool_offset(&mut self) -> Result<u8, Box<dyn std::error::Error>> {
+        return self.v8.pool_offset();
+      }
+
+      // How many values of the current cache are used up. `MAX_NUM_PREDICTIONS - pool_index`
+      // values remain before the next refill. Always 0 for MWC1616, which has no cache.
+      pub fn pool_index(&mut self) -> Result<u8, Box<dyn std::error::Error>> {
+        return self.v8.pool_index();
+      }
+
+      // Starts over with a sequence from the next pool, once this one is exhausted. MWC1616 has
+      // no pool, so starts over straight away.
+      pub fn reset(
+        &mut self,
+        new_sequence: Vec<f64>,
+      ) -> Result<(), $crate::errors::PredictionLimitError> {
+        return self.v8.reset(new_sequence);
+      }
+    }
+  };
+}
+
+pub(crate) use v8_predictor;
+
+#[cfg(test)]
+pub(crate) mod test_values {
+  use super::{CACHE_SIZE, V8Predictor, XorShiftVariant};
+
+  // The state the V8 tests' values are generated from.
+  pub(crate) const TEST_STATE: (u64, u64) = (0x2545F4914F6CDD1D, 0x9E3779B97F4A7C15);
+
+  // The first `n` values Node 24 returns from `TEST_STATE`, see `v8_values_from`.
+  pub(crate) fn v8_values(n: usize) -> Vec<f64> {
+    return v8_values_from(TEST_STATE, XorShiftVariant::Mantissa53, n);
+  }
+
+  // Simulates V8 filling its cache from `state` as many times as it takes, and handing each one
+  // out top index first. The first `n` values, in the order `Math.random()` returns them.
+  pub(crate) fn v8_values_from(
+    (mut state_0, mut state_1): (u64, u64),
+    variant: XorShiftVariant,
+    n: usize,
+  ) -> Vec<f64> {
+    let mut values = vec![];
+    while values.len() < n {
+      let mut cache = vec![];
+      for _ in 0..CACHE_SIZE {
+        V8Predictor::xor_shift_128_plus_forward(&mut state_0, &mut state_1);
+        cache.push(match variant {
+          XorShiftVariant::Mantissa53 => (state_0 >> 11) as f64 / (1u64 << 53) as f64,
+          XorShiftVariant::Mantissa52 => f64::from_bits((state_0 >> 12) | 0x3FF0000000000000) - 1.0,
+        });
+      }
+      values.extend(cache.into_iter().rev());
+    }
+    values.truncate(n);
+    return values;
+  }
+}
@@ -0,0 +1,285 @@
+use crate::{ChromeMajorVersion, NodeJsMajorVersion, errors::VersionError};
+use std::{
+  fmt::{Display, Formatter, Result},
+  str::FromStr,
+};
+
+// What we need to know about a V8 build, to predict its `Math.random()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum V8Variant {
+  // MWC1616 multiply-with-carry, which V8 used before 4.9. 32 bits of state divided by 2^32.
+  Mwc1616,
+  // xorshift128+, with the top 52 bits of state put into the mantissa of a double in [1, 2), minus 1.
+  Mantissa52,
+  // xorshift128+, with the top 53 bits of state divided by 2^53.
+  Mantissa53,
+}
+
+// Only `major.minor` matters, V8 does not change `Math.random()` in patch releases.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct V8Version {
+  pub major: u32,
+  pub minor: u32,
+}
+
+impl V8Version {
+  // First version that uses xorshift128+, instead of MWC1616.
+  const XORSHIFT_128_PLUS: V8Version = V8Version::new(4, 9);
+  // First version that converts 53 bits of state to a double, instead of 52.
+  const MANTISSA_53: V8Version = V8Version::new(13, 3);
+
+  pub const fn new(major: u32, minor: u32) -> Self {
+    return V8Version { major, minor };
+  }
+
+  pub fn variant(&self) -> V8Variant {
+    if *self >= Self::MANTISSA_53 {
+      return V8Variant::Mantissa53;
+    }
+    if *self < Self::XORSHIFT_128_PLUS {
+      return V8Variant::Mwc1616;
+    }
+    return V8Variant::Mantissa52;
+  }
+}
+
+// Accepts V8's own version strings, eg. `12.4.254.21-node.33` from `process.versions.v8`.
+impl FromStr for V8Version {
+  type Err = VersionError;
+
+  fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+    let (major, minor) = parse_major_minor(s)?;
+    return Ok(V8Version::new(major, minor));
+  }
+}
+
+impl Display for V8Version {
+  fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+    return write!(f, "{}.{}", self.major, self.minor);
+  }
+}
+
+impl From<NodeJsMajorVersion> for V8Version {
+  fn from(version: NodeJsMajorVersion) -> Self {
+    // `V0` stands for the 0.x releases, the last of which (0.12) shipped V8 3.28.
+    let minor = if version as u8 == 0 { 12 } else { 0 };
+    return Runtime::Node.v8_version_of(version as u32, minor);
+  }
+}
+
+impl From<ChromeMajorVersion> for V8Version {
+  fn from(version: ChromeMajorVersion) -> Self {
+    return Runtime::Chrome.v8_version_of(version as u32, 0);
+  }
+}
+
+// Everything that embeds V8, and whose version we know how to map to a V8 version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Runtime {
+  Node,
+  Chrome,
+  Deno,
+  Electron,
+}
+
+impl Runtime {
+  // (runtime major, runtime minor, V8 major, V8 minor), a runtime version maps to the last entry
+  // at or below it. Anything newer than the last entry is assumed to behave like it.
+  #[rustfmt::skip]
+  const NODE_V8_VERSIONS: &[(u32, u32, u32, u32)] = &[
+    (0, 0, 3, 14), (0, 12, 3, 28), (1, 0, 4, 1), (2, 0, 4, 2), (3, 0, 4, 4), (4, 0, 4, 5),
+    (5, 0, 4, 6), (6, 0, 5, 0), (7, 0, 5, 4), (8, 0, 5, 8), (9, 0, 6, 2), (10, 0, 6, 6),
+    (11, 0, 7, 0), (12, 0, 7, 4), (13, 0, 7, 8), (14, 0, 8, 1), (15, 0, 8, 6), (16, 0, 9, 0),
+    (17, 0, 9, 5), (18, 0, 10, 1), (19, 0, 10, 7), (20, 0, 11, 3), (21, 0, 11, 8),
+    (22, 0, 12, 4), (23, 0, 12, 9), (24, 0, 13, 6), (25, 0, 14, 1),
+  ];
+  #[rustfmt::skip]
+  const DENO_V8_VERSIONS: &[(u32, u32, u32, u32)] = &[
+    (1, 0, 8, 4), (1, 10, 9, 1), (1, 20, 10, 0), (1, 30, 10, 9), (1, 40, 12, 1), (1, 46, 12, 9),
+    (2, 0, 12, 9), (2, 1, 13, 0), (2, 2, 13, 4), (2, 3, 13, 5), (2, 4, 13, 7), (2, 5, 14, 0),
+  ];
+  // Chrome major version each Electron major shipped with, from Electron 15 on it is `2 * major + 64`.
+  const ELECTRON_CHROME_VERSIONS: &[u32] =
+    &[49, 49, 61, 66, 69, 73, 76, 78, 80, 83, 85, 87, 89, 91, 93];
+
+  // Maps a runtime version string to the V8 version it shipped. Accepts semver (`v22.3.1`,
+  // `22.3`, `22`), or the JSON of `process.versions` (which already includes V8's version).
+  pub fn v8_version(self, version: &str) -> std::result::Result<V8Version, VersionError> {
+    let version = version.trim();
+    if version.starts_with('{') {
+      return self.v8_version_from_process_versions(version);
+    }
+    let (major, minor) = parse_major_minor(version.trim_start_matches(['v', 'V']))?;
+    return Ok(self.v8_version_of(major, minor));
+  }
+
+  pub fn v8_version_of(self, major: u32, minor: u32) -> V8Version {
+    return match self {
+      Runtime::Node => Self::lookup(Self::NODE_V8_VERSIONS, major, minor),
+      Runtime::Deno => Self::lookup(Self::DENO_V8_VERSIONS, major, minor),
+      // Since Chrome 40, Chrome `XY` ships V8 `X.Y`. Before that, Chrome `N` shipped V8 `3.(N - 10)`.
+      Runtime::Chrome if major >= 40 => V8Version::new(major / 10, major % 10),
+      Runtime::Chrome => V8Version::new(3, major.saturating_sub(10)),
+      Runtime::Electron => {
+        let chrome = match Self::ELECTRON_CHROME_VERSIONS.get(major as usize) {
+          Some(&chrome) => chrome,
+          None => 2 * major + 64,
+        };
+        Runtime::Chrome.v8_version_of(chrome, 0)
+      }
+    };
+  }
+
+  // `process.versions` has the exact V8 version, fall back on our own version if it doesn't.
+  #[cfg(feature = "serde")]
+  fn v8_version_from_process_versions(
+    self,
+    json: &str,
+  ) -> std::result::Result<V8Version, VersionError> {
+    let versions: serde_json::Value = serde_json::from_str(json).map_err(|e| {
+      return VersionError::Malformed(e.to_string());
+    })?;
+
+    if let Some(v8) = versions.get("v8").and_then(serde_json::Value::as_str) {
+      return v8.parse();
+    }
+
+    let key = match self {
+      Runtime::Node => "node",
+      Runtime::Chrome => "chrome",
+      Runtime::Deno => "deno",
+      Runtime::Electron => "electron",
+    };
+    let version = versions
+      .get(key)
+      .and_then(serde_json::Value::as_str)
+      .ok_or(VersionError::MissingField(key))?;
+    return self.v8_version(version);
+  }
+
+  #[cfg(not(feature = "serde"))]
+  fn v8_version_from_process_versions(
+    self,
+    _: &str,
+  ) -> std::result::Result<V8Version, VersionError> {
+    return Err(VersionError::NeedsSerde);
+  }
+
+  // Static 'helper' method
+  fn lookup(table: &[(u32, u32, u32, u32)], major: u32, minor: u32) -> V8Version {
+    let mut v8 = V8Version::new(table[0].2, table[0].3);
+    for &(runtime_major, runtime_minor, v8_major, v8_minor) in table {
+      if (runtime_major, runtime_minor) > (major, minor) {
+        break;
+      }
+      v8 = V8Version::new(v8_major, v8_minor);
+    }
+    return v8;
+  }
+}
+
+// Parses the leading `major[.minor]` of a version, ignoring anything after it.
+fn parse_major_minor(version: &str) -> std::result::Result<(u32, u32), VersionError> {
+  let malformed = || {
+    return VersionError::Malformed(version.to_string());
+  };
+  let mut parts = version.split(['.', '-', '+']);
+  let major = match parts.next().map(str::parse) {
+    Some(Ok(major)) => major,
+    _ => return Err(malformed()),
+  };
+  let minor = match parts.next().map(str::parse) {
+    Some(Ok(minor)) => minor,
+    Some(Err(_)) => return Err(malformed()),
+    None => 0,
+  };
+  return Ok((major, minor));
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{ChromeMajorVersion, NodeJsMajorVersion, Runtime, V8Variant, V8Version};
+  use std::error::Error;
+
+  #[test]
+  fn maps_runtime_versions_to_v8() -> Result<(), Box<dyn Error>> {
+    assert_eq!(Runtime::Node.v8_version("v22.3.1")?, V8Version::new(12, 4));
+    assert_eq!(Runtime::Node.v8_version("24")?, V8Version::new(13, 6));
+    assert_eq!(
+      Runtime::Chrome.v8_version("133.0.6943.53")?,
+      V8Version::new(13, 3)
+    );
+    assert_eq!(Runtime::Deno.v8_version("2.1.4")?, V8Version::new(13, 0));
+    assert_eq!(
+      Runtime::Electron.v8_version("v33.2.0")?,
+      V8Version::new(13, 0)
+    );
+    assert_eq!(
+      V8Version::from(NodeJsMajorVersion::V0),
+      V8Version::new(3, 28)
+    );
+    assert_eq!(
+      V8Version::from(ChromeMajorVersion::V120),
+      V8Version::new(12, 0)
+    );
+    return Ok(());
+  }
+
+  #[test]
+  #[cfg(feature = "serde")]
+  fn reads_process_versions() -> Result<(), Box<dyn Error>> {
+    let node = r#"{ "node": "22.3.0", "v8": "12.4.254.21-node.33", "uv": "1.48.0" }"#;
+    assert_eq!(Runtime::Node.v8_version(node)?, V8Version::new(12, 4));
+
+    let without_v8 = r#"{ "deno": "2.2.0" }"#;
+    assert_eq!(Runtime::Deno.v8_version(without_v8)?, V8Version::new(13, 4));
+
+    assert!(Runtime::Node.v8_version(r#"{ "uv": "1.48.0" }"#).is_err());
+    return Ok(());
+  }
+
+  #[test]
+  fn future_majors_use_newest_variant() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+      Runtime::Node.v8_version("v23.11.0")?.variant(),
+      V8Variant::Mantissa52
+    );
+    assert_eq!(
+      Runtime::Node.v8_version("v99")?.variant(),
+      V8Variant::Mantissa53
+    );
+    assert_eq!(
+      Runtime::Chrome.v8_version("180")?.variant(),
+      V8Variant::Mantissa53
+    );
+    assert_eq!(
+      Runtime::Electron.v8_version("60")?.variant(),
+      V8Variant::Mantissa53
+    );
+    assert!(Runtime::Node.v8_version("latest").is_err());
+    return Ok(());
+  }
+
+  #[test]
+  fn legacy_versions_use_mwc1616() -> Result<(), Box<dyn Error>> {
+    assert_eq!(
+      V8Version::from(NodeJsMajorVersion::V5).variant(),
+      V8Variant::Mwc1616
+    );
+    assert_eq!(
+      V8Version::from(NodeJsMajorVersion::V6).variant(),
+      V8Variant::Mantissa52
+    );
+    assert_eq!(
+      Runtime::Chrome.v8_version("48.0.2564.116")?.variant(),
+      V8Variant::Mwc1616
+    );
+    assert_eq!(
+      V8Version::from(ChromeMajorVersion::V49).variant(),
+      V8Variant::Mantissa52
+    );
+    return Ok(());
+  }
+}
@@ -0,0 +1,69 @@
+use crate::{Observation, errors::InitError};
+
+// What the solved state returns for each call of the sequence, next to what was observed there.
+// Solving checks this before predicting anything, so a state that only fit the solver's model of
+// the generator, and not the generator itself, is an error instead of confident-looking guesses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+  pub observations: Vec<Observation>,
+  pub replayed: Vec<f64>,
+  // Indices of the observations the replayed values don't match.
+  pub mismatches: Vec<usize>,
+}
+
+impl Verification {
+  pub(crate) fn new(observations: &[Observation], replayed: Vec<f64>) -> Self {
+    let mismatches = observations
+      .iter()
+      .zip(&replayed)
+      .enumerate()
+      .filter(|(_, (observation, value))| {
+        return !observation.matches(**value);
+      })
+      .map(|(i, _)| {
+        return i;
+      })
+      .collect();
+    return Verification {
+      observations: observations.to_vec(),
+      replayed,
+      mismatches,
+    };
+  }
+
+  pub fn is_verified(&self) -> bool {
+    return self.mismatches.is_empty();
+  }
+
+  // `InitError::Unverified` naming the mismatches, if there are any.
+  pub(crate) fn check(&self) -> Result<(), InitError> {
+    if !self.is_verified() {
+      return Err(InitError::Unverified(self.mismatches.clone()));
+    }
+    return Ok(());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::Verification;
+  use crate::{Observation, errors::InitError};
+
+  #[test]
+  fn names_the_observations_the_replay_misses() -> Result<(), InitError> {
+    let observations = vec![
+      Observation::Exact(0.25),
+      Observation::Unknown,
+      Observation::Exact(0.5),
+      Observation::integer(3, 6)?,
+    ];
+    let verification = Verification::new(&observations, vec![0.25, 0.1, 0.5000000000000001, 0.6]);
+    assert!(!verification.is_verified());
+    assert_eq!(verification.mismatches, vec![2]);
+    assert!(matches!(
+      verification.check(),
+      Err(InitError::Unverified(indices)) if indices == vec![2]
+    ));
+    return Ok(());
+  }
+}